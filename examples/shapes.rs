@@ -49,6 +49,7 @@ fn setup(
             brightness: 3.0,
             edges_transition_size: 0.025,
             channels_mask_min: 0.1,
+            ..default()
         },
     ));
 
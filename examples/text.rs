@@ -57,6 +57,7 @@ fn setup(In(use_3d_camera): In<bool>, mut commands: Commands) {
             brightness: 3.0,
             edges_transition_size: 0.025,
             channels_mask_min: 0.1,
+            ..default()
         },
     );
     // Text with one section
@@ -0,0 +1,144 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_old_tv_shader::common::{
+    texture_sampler, settings, globals, RGB_TO_YIQ, YIQ_TO_RGB, encode_gamma, decode_gamma,
+}
+
+// The emulated pixels
+@group(1) @binding(0) var picture_texture: texture_2d<f32>;
+
+#ifdef NTSC
+// The 3.58MHz color subcarrier, in cycles over the 52µs of a visible line
+const SUBCARRIER: f32 = 188.3;
+// The low pass filter separating luma from the subcarrier, in cycles per line
+const LUMA_BANDWIDTH: f32 = 80.0;
+// The 1.3MHz I and 0.4MHz Q chroma, in cycles per line
+const CHROMA_BANDWIDTH: vec2<f32> = vec2(68.4, 21.0);
+
+// Returns the luma and the chroma components modulating the cosine and the
+// sine of the subcarrier.
+fn encode(rgb: vec3<f32>, row: f32) -> vec3<f32> {
+    return RGB_TO_YIQ * rgb;
+}
+
+// Returns the luma and chroma components of the standard.
+fn decode(signal: vec3<f32>, row: f32) -> vec3<f32> {
+    return signal;
+}
+
+fn to_rgb(yiq: vec3<f32>) -> vec3<f32> {
+    return YIQ_TO_RGB * yiq;
+}
+#else ifdef PAL
+// The 4.43MHz color subcarrier, in cycles over the 52µs of a visible line
+const SUBCARRIER: f32 = 230.5;
+// The low pass filter separating luma from the subcarrier, in cycles per line
+const LUMA_BANDWIDTH: f32 = 100.0;
+// The 1.3MHz U and V chroma, in cycles per line
+const CHROMA_BANDWIDTH: vec2<f32> = vec2(67.6, 67.6);
+
+const RGB_TO_YUV: mat3x3<f32> = mat3x3<f32>(
+    vec3(0.299, -0.14713, 0.615),
+    vec3(0.587, -0.28886, -0.51499),
+    vec3(0.114, 0.436, -0.10001),
+);
+const YUV_TO_RGB: mat3x3<f32> = mat3x3<f32>(
+    vec3(1.0, 1.0, 1.0),
+    vec3(0.0, -0.39465, 2.03211),
+    vec3(1.13983, -0.5806, 0.0),
+);
+
+// The sign of V, switched on every other row.
+fn v_switch(row: f32) -> f32 {
+    return select(1.0, -1.0, u32(abs(row)) % 2u == 1u);
+}
+
+// Returns the luma and the chroma components modulating the cosine and the
+// sine of the subcarrier.
+fn encode(rgb: vec3<f32>, row: f32) -> vec3<f32> {
+    let yuv = RGB_TO_YUV * rgb;
+    return vec3(yuv.x, yuv.z * v_switch(row), yuv.y);
+}
+
+// Returns the luma and chroma components of the standard.
+fn decode(signal: vec3<f32>, row: f32) -> vec3<f32> {
+    return vec3(signal.x, signal.z, signal.y * v_switch(row));
+}
+
+fn to_rgb(yuv: vec3<f32>) -> vec3<f32> {
+    return YUV_TO_RGB * yuv;
+}
+#endif
+
+// Taps on each side of the chroma filter at most, whatever the resolution
+const MAX_TAPS: i32 = 48;
+
+// The standard deviation of a gaussian filter cutting at the frequency.
+fn gaussian_sigma(bandwidth: f32) -> f32 {
+    return 0.1325 / bandwidth;
+}
+
+fn gaussian(x: vec3<f32>, sigma: vec3<f32>) -> vec3<f32> {
+    return exp(-x * x / (2.0 * sigma * sigma));
+}
+
+// Encodes the row around the uv into a composite signal, sampled four times per
+// subcarrier period, and decodes it back with low pass filters. Without
+// artifacts, luma and chroma are only filtered apart from each other.
+fn receive(uv: vec2<f32>, row: f32) -> vec3<f32> {
+    let phase = fract(row * settings.subcarrier_line_phase)
+        + fract(f32(globals.frame) * settings.subcarrier_frame_phase);
+
+    let step = 1.0 / (4.0 * SUBCARRIER);
+    let sigma = vec3(
+        gaussian_sigma(LUMA_BANDWIDTH),
+        gaussian_sigma(CHROMA_BANDWIDTH.x),
+        gaussian_sigma(CHROMA_BANDWIDTH.y),
+    );
+    let taps = min(i32(ceil(3.0 * max(sigma.y, sigma.z) / step)), MAX_TAPS);
+
+    var clean = vec3(0.0);
+    var artifacts = vec3(0.0);
+    var weights = vec3(0.0);
+    for (var i = -taps; i <= taps; i++) {
+        let offset = f32(i) * step;
+        let x = uv.x + offset;
+        let rgb = textureSampleLevel(picture_texture, texture_sampler, vec2(x, uv.y), 0.0).rgb;
+        // The signal carries gamma encoded colors.
+        let components = encode(encode_gamma(rgb), row);
+
+        let angle = 6.2831855 * (x * SUBCARRIER + phase);
+        let carrier = vec2(cos(angle), sin(angle));
+        let signal = components.x + dot(components.yz, carrier);
+
+        let weight = gaussian(vec3(offset), sigma);
+        clean += components * weight;
+        artifacts += vec3(signal, 2.0 * signal * carrier) * weight;
+        weights += weight;
+    }
+    let signal = mix(clean, artifacts, settings.composite_artifacts) / weights;
+
+    // A decoder out of phase with the subcarrier rotates the chroma.
+    let error = 6.2831855 * settings.subcarrier_phase_error;
+    let rotation = mat2x2<f32>(cos(error), sin(error), -sin(error), cos(error));
+    return decode(vec3(signal.x, rotation * signal.yz), row);
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let row = floor(in.position.y);
+    var components = receive(in.uv, row);
+
+#ifdef PAL
+    // The delay line averages the chroma with the previous row, where the
+    // phase errors go the other way.
+    if settings.pal_delay_line == 1u {
+        let row_height = 1.0 / f32(textureDimensions(picture_texture).y);
+        let previous = receive(in.uv - vec2(0.0, row_height), row - 1.0);
+        components = vec3(components.x, (components.yz + previous.yz) * 0.5);
+    }
+#endif
+
+    let rgb = decode_gamma(to_rgb(components));
+    let alpha = textureSampleLevel(picture_texture, texture_sampler, in.uv, 0.0).a;
+    return vec4(rgb, alpha);
+}
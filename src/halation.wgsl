@@ -0,0 +1,44 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_old_tv_shader::common::{linear_sampler, settings, emulated_size}
+
+// The emulated pixels for the horizontal pass, its output for the vertical one
+@group(1) @binding(0) var input_texture: texture_2d<f32>;
+
+// Bounds the taps of each pass, whatever the radius.
+const MAX_TAPS: i32 = 32;
+
+fn tap(uv: vec2<f32>) -> vec3<f32> {
+    let color = textureSampleLevel(input_texture, linear_sampler, uv, 0.0).rgb;
+#ifdef HALATION_HORIZONTAL
+    // Only the light above the threshold glows.
+    return max(color - vec3(settings.halation_threshold), vec3(0.0));
+#else
+    return color;
+#endif
+}
+
+// A gaussian blur with one tap every two emulated pixels, the resolution of
+// the output. The horizontal pass samples between four emulated pixels, so
+// the filtering sampler averages them.
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+#ifdef HALATION_HORIZONTAL
+    let direction = vec2(2.0, 0.0) / emulated_size();
+#else
+    let direction = vec2(0.0, 2.0) / emulated_size();
+#endif
+    // The glow fades out at the radius.
+    let sigma = settings.halation_radius / 3.0;
+    let taps = min(i32(ceil(settings.halation_radius / 2.0)), MAX_TAPS);
+
+    var sum = vec3(0.0);
+    var weights = 0.0;
+    for (var i = -taps; i <= taps; i++) {
+        let distance = f32(i) * 2.0;
+        let weight = exp(-distance * distance / (2.0 * sigma * sigma));
+        sum += tap(in.uv + f32(i) * direction) * weight;
+        weights += weight;
+    }
+
+    return vec4(sum / weights, 1.0);
+}
@@ -0,0 +1,19 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_old_tv_shader::common::{texture_sampler, globals}
+
+// The emulated pixels of this frame
+@group(1) @binding(0) var picture_texture: texture_2d<f32>;
+// The emulated pixels of the previous frame
+@group(1) @binding(1) var previous_texture: texture_2d<f32>;
+
+// Weaves the lines of the current field from this frame with the lines of
+// the other field from the previous frame. The screen pass dims the latter.
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let field = globals.frame % 2u;
+    let line = u32(in.position.y);
+    if line % 2u == field {
+        return textureSampleLevel(picture_texture, texture_sampler, in.uv, 0.0);
+    }
+    return textureSampleLevel(previous_texture, texture_sampler, in.uv, 0.0);
+}
@@ -2,7 +2,8 @@
 #![doc = include_str!("../README.md")]
 #![forbid(missing_docs)]
 use bevy::{
-    asset::embedded_asset,
+    app::SubApp,
+    asset::{embedded_asset, load_internal_asset},
     core_pipeline::{
         core_2d::graph::{Core2d, Node2d},
         core_3d::graph::{Core3d, Node3d},
@@ -15,26 +16,43 @@ use bevy::{
             ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
             UniformComponentPlugin,
         },
+        render_asset::RenderAssets,
         render_graph::{
-            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+            NodeRunError, RenderGraph, RenderGraphApp, RenderGraphContext, RenderLabel,
+            RenderSubGraph, ViewNode, ViewNodeRunner,
         },
         render_resource::{
             binding_types::{sampler, texture_2d, uniform_buffer},
             *,
         },
         renderer::{RenderContext, RenderDevice},
-        view::ViewTarget,
-        RenderApp,
+        sync_component::SyncComponentPlugin,
+        sync_world::RenderEntity,
+        texture::{CachedTexture, FallbackImageZero, GpuImage, TextureCache},
+        view::{ExtractedView, ViewTarget},
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
     },
+    utils::HashMap,
 };
 #[cfg(feature = "ui")]
 use bevy_ui::graph::NodeUi;
 
 /// Useful splat imports
 pub mod prelude {
-    pub use super::{OldTvPlugin, OldTvSettings};
+    pub use super::{
+        OldTvBezel, OldTvClock, OldTvCurvature, OldTvFilter, OldTvGhost, OldTvMask, OldTvMaskPitch,
+        OldTvNoise, OldTvPhosphor, OldTvPlacement, OldTvPlugin, OldTvPrimaries, OldTvSettings,
+        OldTvSignal, OldTvStages, OldTvTime,
+    };
 }
 
+// The module imported by the shaders of all passes. Unlike the pass shaders,
+// it is loaded while building the plugin: the pipeline cache doesn't wait for
+// imported modules that are still loading, and fails the pipelines importing
+// them instead.
+const OLD_TV_COMMON_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x6f1d_2c8a_93b4_4e57_a0c1_5b7e_d94f_3a62);
+
 /// Old TV plugin
 ///
 /// Makes the old TV post-processing effect available.
@@ -44,14 +62,34 @@ pub struct OldTvPlugin;
 
 impl Plugin for OldTvPlugin {
     fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            OLD_TV_COMMON_SHADER_HANDLE,
+            "old_tv_common.wgsl",
+            Shader::from_wgsl
+        );
+        embedded_asset!(app, "pixelate.wgsl");
+        embedded_asset!(app, "vhs.wgsl");
+        embedded_asset!(app, "composite.wgsl");
+        embedded_asset!(app, "persistence.wgsl");
+        embedded_asset!(app, "interlace.wgsl");
+        embedded_asset!(app, "halation.wgsl");
         embedded_asset!(app, "old_tv.wgsl");
-        app.register_type::<OldTvSettings>().add_plugins((
-            // The settings will be a component that lives in the main world but will
-            // be extracted to the render world every frame.
-            ExtractComponentPlugin::<OldTvSettings>::default(),
-            // The settings will also be the data used in the shader.
-            UniformComponentPlugin::<OldTvSettings>::default(),
-        ));
+        app.register_type::<OldTvSettings>()
+            .register_type::<OldTvPlacement>()
+            .register_type::<OldTvClock>()
+            .register_type::<OldTvTime>()
+            .add_systems(PostUpdate, advance_old_tv_clocks)
+            .add_plugins((
+                // The settings will be a component that lives in the main world but will
+                // be extracted to the render world every frame by [`extract_old_tv_settings`].
+                SyncComponentPlugin::<OldTvSettings>::default(),
+                ExtractComponentPlugin::<OldTvPlacement>::default(),
+                // The settings are extracted along with the data used in the shader.
+                UniformComponentPlugin::<OldTvUniform>::default(),
+                // The globals are extracted by [`extract_old_tv_globals`] instead.
+                UniformComponentPlugin::<OldTvGlobals>::default(),
+            ));
 
         // We need to get the render app from the main app
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
@@ -59,33 +97,85 @@ impl Plugin for OldTvPlugin {
         };
 
         render_app
-            // The [`ViewNodeRunner`] is a special [`Node`] that will automatically run the node for each view
-            // matching the [`ViewQuery`]
-            .add_render_graph_node::<ViewNodeRunner<OldTvNode>>(
-                // Specify the label of the graph, in this case we want the graph for 3d
-                Core3d, // It also needs the label of the node
-                OldTvLabel,
+            // Pipelines are specialized per view, so the cache lives in the render world
+            .init_resource::<SpecializedRenderPipelines<OldTvPipeline>>()
+            .add_systems(
+                ExtractSchedule,
+                (extract_old_tv_settings, extract_old_tv_globals),
             )
-            .add_render_graph_node::<ViewNodeRunner<OldTvNode>>(Core2d, OldTvLabel);
-        #[cfg(feature = "ui")]
-        render_app
-            .add_render_graph_edges(Core2d, (NodeUi::UiPass, OldTvLabel, Node2d::Upscaling))
-            .add_render_graph_edges(Core3d, (NodeUi::UiPass, OldTvLabel, Node3d::Upscaling));
-        #[cfg(not(feature = "ui"))]
+            .add_systems(
+                Render,
+                prepare_old_tv_passes.in_set(RenderSet::PrepareResources),
+            );
+
+        // There is one node per placement in each graph. Every node only runs
+        // for the cameras whose [`OldTvPlacement`] matches its own, so the
+        // placement can be picked per camera at runtime.
+        for placement in OldTvPlacement::ALL {
+            add_old_tv_node(render_app, Core2d, placement, OldTvPlacement::DEFAULT_2D);
+            add_old_tv_node(render_app, Core3d, placement, OldTvPlacement::DEFAULT_3D);
+        }
+
+        // Specify the node ordering.
+        // This will automatically create all required node edges to enforce the given ordering.
         render_app
             .add_render_graph_edges(
                 Core2d,
-                (Node2d::EndMainPass, OldTvLabel, Node2d::Tonemapping),
+                (
+                    Node2d::EndMainPass,
+                    OldTvLabel(OldTvPlacement::BeforeTonemapping),
+                    Node2d::Tonemapping,
+                ),
+            )
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::EndMainPass,
+                    OldTvLabel(OldTvPlacement::BeforeTonemapping),
+                    Node3d::Tonemapping,
+                ),
+            )
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::Tonemapping,
+                    OldTvLabel(OldTvPlacement::AfterTonemapping),
+                    Node2d::EndMainPassPostProcessing,
+                ),
             )
             .add_render_graph_edges(
                 Core3d,
-                // Specify the node ordering.
-                // This will automatically create all required node edges to enforce the given ordering.
                 (
                     Node3d::Tonemapping,
-                    OldTvLabel,
+                    OldTvLabel(OldTvPlacement::AfterTonemapping),
                     Node3d::EndMainPassPostProcessing,
                 ),
+            )
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::EndMainPassPostProcessing,
+                    OldTvLabel(OldTvPlacement::AfterUi),
+                    Node2d::Upscaling,
+                ),
+            )
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::EndMainPassPostProcessing,
+                    OldTvLabel(OldTvPlacement::AfterUi),
+                    Node3d::Upscaling,
+                ),
+            );
+        #[cfg(feature = "ui")]
+        render_app
+            .add_render_graph_edges(
+                Core2d,
+                (NodeUi::UiPass, OldTvLabel(OldTvPlacement::AfterUi)),
+            )
+            .add_render_graph_edges(
+                Core3d,
+                (NodeUi::UiPass, OldTvLabel(OldTvPlacement::AfterUi)),
             );
     }
 
@@ -101,12 +191,38 @@ impl Plugin for OldTvPlugin {
     }
 }
 
+// Adds the node running the effect at `placement` to `sub_graph`.
+fn add_old_tv_node(
+    render_app: &mut SubApp,
+    sub_graph: impl RenderSubGraph,
+    placement: OldTvPlacement,
+    default_placement: OldTvPlacement,
+) {
+    // The [`ViewNodeRunner`] is a special [`Node`] that will automatically run the node for each view
+    // matching the [`ViewQuery`]
+    let node = ViewNodeRunner::new(
+        OldTvNode {
+            placement,
+            default_placement,
+        },
+        render_app.world_mut(),
+    );
+    let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+    if let Some(graph) = render_graph.get_sub_graph_mut(sub_graph) {
+        graph.add_node(OldTvLabel(placement), node);
+    }
+}
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
-struct OldTvLabel;
+struct OldTvLabel(OldTvPlacement);
 
 // The post process node used for the render graph
-#[derive(Default)]
-struct OldTvNode;
+struct OldTvNode {
+    // Views are only processed by the node matching their placement
+    placement: OldTvPlacement,
+    // The placement of the views without [`OldTvPlacement`] in this graph
+    default_placement: OldTvPlacement,
+}
 
 // The ViewNode trait is required by the ViewNodeRunner
 impl ViewNode for OldTvNode {
@@ -116,11 +232,17 @@ impl ViewNode for OldTvNode {
     // This query will only run on the view entity
     type ViewQuery = (
         &'static ViewTarget,
+        // The passes prepared for this view in [`prepare_old_tv_passes`]
+        &'static ViewOldTvPasses,
         // This makes sure the node only runs on cameras with the OldTvSettings component
         &'static OldTvSettings,
+        // Where in the graph the camera wants the effect, if not the default
+        Option<&'static OldTvPlacement>,
         // As there could be multiple post processing components sent to the GPU (one per camera),
         // we need to get the index of the one that is associated with the current view.
-        &'static DynamicUniformIndex<OldTvSettings>,
+        &'static DynamicUniformIndex<OldTvUniform>,
+        // Same for the per-view globals (resolution, time, frame and seed)
+        &'static DynamicUniformIndex<OldTvGlobals>,
     );
 
     // Runs the node logic
@@ -134,11 +256,30 @@ impl ViewNode for OldTvNode {
         &self,
         _graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        (view_target, _post_process_settings, settings_index): QueryItem<Self::ViewQuery>,
+        (
+            view_target,
+            view_passes,
+            _post_process_settings,
+            placement,
+            settings_index,
+            globals_index,
+        ): QueryItem<Self::ViewQuery>,
         world: &World,
     ) -> Result<(), NodeRunError> {
+        // Another node in the graph handles this view
+        if placement.copied().unwrap_or(self.default_placement) != self.placement {
+            return Ok(());
+        }
+        #[cfg(not(feature = "ui"))]
+        if self.placement == OldTvPlacement::AfterUi {
+            warn_once!(
+                "OldTvPlacement::AfterUi is not ordered after the UI pass without the \"ui\" \
+                feature, the UI may be drawn over the effect"
+            );
+        }
+
         // Get the pipeline resource that contains the global data we need
-        // to create the render pipeline
+        // to create the render pipelines
         let old_tv_pipeline = world.resource::<OldTvPipeline>();
 
         // The pipeline cache is a cache of all previously created pipelines.
@@ -146,17 +287,29 @@ impl ViewNode for OldTvNode {
         // which is expensive due to shader compilation.
         let pipeline_cache = world.resource::<PipelineCache>();
 
-        // Get the pipeline from the cache
-        let Some(pipeline) = pipeline_cache.get_render_pipeline(old_tv_pipeline.pipeline_id) else {
-            return Ok(());
-        };
+        // Get the pipelines specialized for this view from the cache. All of
+        // them must be ready before starting the post process write, or the
+        // destination would be left without the picture.
+        let mut pipelines = Vec::with_capacity(view_passes.passes.len());
+        for pass in &view_passes.passes {
+            let Some(pipeline) = pipeline_cache.get_render_pipeline(pass.pipeline_id) else {
+                return Ok(());
+            };
+            pipelines.push(pipeline);
+        }
 
         // Get the settings uniform binding
-        let settings_uniforms = world.resource::<ComponentUniforms<OldTvSettings>>();
+        let settings_uniforms = world.resource::<ComponentUniforms<OldTvUniform>>();
         let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
             return Ok(());
         };
 
+        // Get the globals uniform binding
+        let globals_uniforms = world.resource::<ComponentUniforms<OldTvGlobals>>();
+        let Some(globals_binding) = globals_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
         // This will start a new "post process write", obtaining two texture
         // views from the view target - a `source` and a `destination`.
         // `source` is the "current" main texture and you _must_ write into
@@ -166,156 +319,1819 @@ impl ViewNode for OldTvNode {
         // the current main texture information to be lost.
         let post_process = view_target.post_process_write();
 
-        // The bind_group gets created each frame.
+        // The bind groups get created each frame.
         //
         // Normally, you would create a bind_group in the Queue set,
         // but this doesn't work with the post_process_write().
         // The reason it doesn't work is because each post_process_write will alternate the source/destination.
         // The only way to have the correct source/destination for the bind_group
         // is to make sure you get it during the node execution.
-        let bind_group = render_context.render_device().create_bind_group(
-            "old_tv_bind_group",
-            &old_tv_pipeline.layout,
+        let view_bind_group = render_context.render_device().create_bind_group(
+            "old_tv_view_bind_group",
+            &old_tv_pipeline.view_layout,
             // It's important for this to match the BindGroupLayout defined in the OldTvPipeline
             &BindGroupEntries::sequential((
-                // Make sure to use the source view
-                post_process.source,
                 // Use the sampler created for the pipeline
                 &old_tv_pipeline.sampler,
                 // Set the settings binding
                 settings_binding.clone(),
+                // Set the globals binding
+                globals_binding.clone(),
+                // Use the filtering sampler created for the pipeline
+                &old_tv_pipeline.linear_sampler,
             )),
         );
 
-        // Begin the render pass
-        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
-            label: Some("old_tv_pass"),
-            color_attachments: &[Some(RenderPassColorAttachment {
-                // We need to specify the post process destination view here
-                // to make sure we write to the appropriate texture.
-                view: post_process.destination,
-                resolve_target: None,
-                ops: Operations::default(),
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
-        });
+        let texture_view = |texture: OldTvTexture| match texture {
+            // Make sure to use the source view
+            OldTvTexture::Source => post_process.source,
+            texture => &view_passes.textures[&texture].default_view,
+        };
+
+        for (pass, pipeline) in view_passes.passes.iter().zip(pipelines) {
+            let input_entries = pass
+                .inputs
+                .iter()
+                .enumerate()
+                .map(|(binding, &texture)| BindGroupEntry {
+                    binding: binding as u32,
+                    resource: BindingResource::TextureView(texture_view(texture)),
+                })
+                .collect::<Vec<_>>();
+            let input_bind_group = render_context.render_device().create_bind_group(
+                "old_tv_input_bind_group",
+                &old_tv_pipeline.input_layouts[pass.inputs.len() - 1],
+                &input_entries,
+            );
+
+            // We need to specify the post process destination view for the
+            // last pass to make sure we write to the appropriate texture.
+            let output = match pass.output {
+                Some(texture) => texture_view(texture),
+                None => post_process.destination,
+            };
+
+            // Begin the render pass
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some(pass.pass.label()),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: output,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
 
-        // This is mostly just wgpu boilerplate for drawing a fullscreen triangle,
-        // using the pipeline/bind_group created above
-        render_pass.set_render_pipeline(pipeline);
-        // By passing in the index of the post process settings on this view, we ensure
-        // that in the event that multiple settings were sent to the GPU (as would be the
-        // case with multiple cameras), we use the correct one.
-        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
-        render_pass.draw(0..3, 0..1);
+            // This is mostly just wgpu boilerplate for drawing a fullscreen triangle,
+            // using the pipeline/bind_groups created above
+            render_pass.set_render_pipeline(pipeline);
+            // By passing in the index of the post process settings on this view, we ensure
+            // that in the event that multiple settings were sent to the GPU (as would be the
+            // case with multiple cameras), we use the correct one.
+            // The offsets are given in the order of the bindings.
+            render_pass.set_bind_group(
+                0,
+                &view_bind_group,
+                &[settings_index.index(), globals_index.index()],
+            );
+            render_pass.set_bind_group(1, &input_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
 
         Ok(())
     }
 }
 
-// This contains global data used by the render pipeline. This will be created once on startup.
+// The fullscreen passes the effect is made of, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum OldTvPass {
+    // Samples the source once per emulated pixel.
+    Pixelate,
+    // Plays the emulated pixels back from a tape.
+    Vhs,
+    // Sends the emulated pixels through a composite video signal.
+    Composite,
+    // Blends the emulated pixels with the glow left from previous frames.
+    Persistence,
+    // Shows the previous frame on the lines of the field not drawn this frame.
+    Interlace,
+    // Keeps the bright parts of the picture and blurs them horizontally at
+    // half the emulated resolution.
+    HalationHorizontal,
+    // Blurs the output of `HalationHorizontal` vertically.
+    HalationVertical,
+    // Turns the emulated pixels into the picture on the screen.
+    Screen,
+}
+
+impl OldTvPass {
+    const ALL: [Self; 8] = [
+        Self::Pixelate,
+        Self::Vhs,
+        Self::Composite,
+        Self::Persistence,
+        Self::Interlace,
+        Self::HalationHorizontal,
+        Self::HalationVertical,
+        Self::Screen,
+    ];
+    // The most textures read by a pass
+    const MAX_INPUTS: usize = 4;
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Pixelate => "old_tv_pixelate_pass",
+            Self::Vhs => "old_tv_vhs_pass",
+            Self::Composite => "old_tv_composite_pass",
+            Self::Persistence => "old_tv_persistence_pass",
+            Self::Interlace => "old_tv_interlace_pass",
+            Self::HalationHorizontal => "old_tv_halation_horizontal_pass",
+            Self::HalationVertical => "old_tv_halation_vertical_pass",
+            Self::Screen => "old_tv_screen_pass",
+        }
+    }
+
+    // The stages whose shader defs the pass reads.
+    fn stages(self) -> OldTvStages {
+        match self {
+            Self::Pixelate => OldTvStages::SNOW,
+            Self::Screen => {
+                OldTvStages::all()
+                    - OldTvStages::SNOW
+                    - OldTvStages::COMPOSITE
+                    - OldTvStages::VHS
+                    - OldTvStages::PERSISTENCE
+            }
+            Self::Vhs
+            | Self::Composite
+            | Self::Persistence
+            | Self::Interlace
+            | Self::HalationHorizontal
+            | Self::HalationVertical => OldTvStages::empty(),
+        }
+    }
+
+    fn shader_path(self) -> &'static str {
+        match self {
+            Self::Pixelate => "embedded://bevy_old_tv_shader/pixelate.wgsl",
+            Self::Vhs => "embedded://bevy_old_tv_shader/vhs.wgsl",
+            Self::Composite => "embedded://bevy_old_tv_shader/composite.wgsl",
+            Self::Persistence => "embedded://bevy_old_tv_shader/persistence.wgsl",
+            Self::Interlace => "embedded://bevy_old_tv_shader/interlace.wgsl",
+            Self::HalationHorizontal | Self::HalationVertical => {
+                "embedded://bevy_old_tv_shader/halation.wgsl"
+            }
+            Self::Screen => "embedded://bevy_old_tv_shader/old_tv.wgsl",
+        }
+    }
+}
+
+// The textures the passes read from and write to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum OldTvTexture {
+    // The view's main texture before the effect
+    Source,
+    // One texel per emulated pixel
+    Pixelated,
+    // The emulated pixels played back from the tape
+    Vhs,
+    // The emulated pixels decoded from the composite signal
+    Composite,
+    // The emulated pixels with the phosphor glow, kept for the next frame
+    History,
+    // The `History` written by the previous frame
+    PreviousHistory,
+    // The picture with both fields
+    Interlaced,
+    // The bright parts of the picture blurred horizontally
+    HalationHorizontal,
+    // The glow spread around the bright parts of the picture
+    Halation,
+    // The image of [`OldTvSettings::bezel`], never taken from the texture cache
+    Bezel,
+}
+
+impl OldTvTexture {
+    // Intermediate textures use a float format so HDR values survive.
+    const FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+    // Keeps absurd settings from requesting textures the GPU can't create.
+    const MAX_SIZE: u32 = 4096;
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Source => "old_tv_source_texture",
+            Self::Pixelated => "old_tv_pixelated_texture",
+            Self::Vhs => "old_tv_vhs_texture",
+            Self::Composite => "old_tv_composite_texture",
+            Self::History | Self::PreviousHistory => "old_tv_history_texture",
+            Self::Interlaced => "old_tv_interlaced_texture",
+            Self::HalationHorizontal => "old_tv_halation_horizontal_texture",
+            Self::Halation => "old_tv_halation_texture",
+            Self::Bezel => "old_tv_bezel_texture",
+        }
+    }
+
+    // The size of the texture for a view of `viewport_size` physical pixels.
+    fn size(self, viewport_size: UVec2, settings: &OldTvSettings) -> UVec2 {
+        match self {
+            Self::Source | Self::Bezel => viewport_size,
+            Self::Pixelated
+            | Self::Vhs
+            | Self::Composite
+            | Self::History
+            | Self::PreviousHistory
+            | Self::Interlaced => Self::emulated_size(viewport_size, settings)
+                .ceil()
+                .as_uvec2(),
+            // The glow is smooth, so half the resolution is plenty.
+            Self::HalationHorizontal | Self::Halation => {
+                (Self::emulated_size(viewport_size, settings) / 2.0)
+                    .ceil()
+                    .as_uvec2()
+            }
+        }
+        .clamp(UVec2::ONE, UVec2::splat(Self::MAX_SIZE))
+    }
+
+    // Number of emulated columns (x) and rows (y), limited to what the textures
+    // can hold. The shaders read it from `OldTvGlobals`.
+    fn emulated_size(viewport_size: UVec2, settings: &OldTvSettings) -> Vec2 {
+        let screen_size = viewport_size.as_vec2() * settings.screen().size();
+        let ratio = screen_size.x / screen_size.y.max(1.0);
+        let rows = settings.rows;
+        Vec2::new(rows * ratio, rows).clamp(Vec2::ONE, Vec2::splat(Self::MAX_SIZE as f32))
+    }
+
+    fn descriptor(self, size: UVec2) -> TextureDescriptor<'static> {
+        TextureDescriptor {
+            label: Some(self.label()),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        }
+    }
+}
+
+// One pass of the chain prepared for a view.
+#[derive(Clone)]
+struct ViewOldTvPass {
+    pass: OldTvPass,
+    pipeline_id: CachedRenderPipelineId,
+    // The textures read by the pass, bound in this order in group 1
+    inputs: Vec<OldTvTexture>,
+    // Where the pass draws, `None` for the view's destination texture
+    output: Option<OldTvTexture>,
+}
+
+// The chain of passes of a view, with the intermediate textures they use.
+#[derive(Component)]
+struct ViewOldTvPasses {
+    passes: Vec<ViewOldTvPass>,
+    textures: HashMap<OldTvTexture, CachedTexture>,
+    // The format the last pass was specialized for
+    main_texture_format: TextureFormat,
+}
+
+// This contains global data used by the render pipelines. This will be created once on startup.
 #[derive(Resource)]
 struct OldTvPipeline {
-    layout: BindGroupLayout,
+    // The sampler and uniforms shared by all passes, bound in group 0
+    view_layout: BindGroupLayout,
+    // The textures read by the passes, bound in group 1. The layout at index
+    // `i` has `i + 1` textures.
+    input_layouts: Vec<BindGroupLayout>,
     sampler: Sampler,
-    pipeline_id: CachedRenderPipelineId,
+    // Blends neighboring texels, for the passes that resample a picture
+    linear_sampler: Sampler,
+    shaders: HashMap<OldTvPass, Handle<Shader>>,
 }
 
 impl FromWorld for OldTvPipeline {
     fn from_world(world: &mut World) -> Self {
         let render_device = world.resource::<RenderDevice>();
 
-        // We need to define the bind group layout used for our pipeline
-        let layout = render_device.create_bind_group_layout(
-            "old_tv_bind_group_layout",
+        // We need to define the bind group layouts used for our pipelines
+        let view_layout = render_device.create_bind_group_layout(
+            "old_tv_view_bind_group_layout",
             &BindGroupLayoutEntries::sequential(
                 // The layout entries will only be visible in the fragment stage
                 ShaderStages::FRAGMENT,
                 (
-                    // The screen texture
-                    texture_2d(TextureSampleType::Float { filterable: true }),
-                    // The sampler that will be used to sample the screen texture
+                    // The sampler that will be used to sample the textures
                     sampler(SamplerBindingType::Filtering),
                     // The settings uniform that will control the effect
-                    uniform_buffer::<OldTvSettings>(true),
+                    uniform_buffer::<OldTvUniform>(true),
+                    // The view resolution and the time, frame and seed used to animate the effect
+                    uniform_buffer::<OldTvGlobals>(true),
+                    // The sampler that will be used to resample the textures smoothly
+                    sampler(SamplerBindingType::Filtering),
                 ),
             ),
         );
+        let input_layouts = (1..=OldTvPass::MAX_INPUTS as u32)
+            .map(|count| {
+                let entries = (0..count)
+                    .map(|binding| {
+                        texture_2d(TextureSampleType::Float { filterable: true })
+                            .build(binding, ShaderStages::FRAGMENT)
+                    })
+                    .collect::<Vec<_>>();
+                render_device
+                    .create_bind_group_layout("old_tv_input_bind_group_layout", entries.as_slice())
+            })
+            .collect();
 
-        // We can create the sampler here since it won't change at runtime and doesn't depend on the view
+        // We can create the samplers here since they won't change at runtime and don't depend on the view
         let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+        let linear_sampler = render_device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
 
-        // Get the shader handle
-        let shader = world.load_asset("embedded://bevy_old_tv_shader/old_tv.wgsl");
-
-        let pipeline_id = world
-            .resource_mut::<PipelineCache>()
-            // This will add the pipeline to the cache and queue its creation
-            .queue_render_pipeline(RenderPipelineDescriptor {
-                label: Some("old_tv_pipeline".into()),
-                layout: vec![layout.clone()],
-                // This will setup a fullscreen triangle for the vertex state
-                vertex: fullscreen_shader_vertex_state(),
-                fragment: Some(FragmentState {
-                    shader,
-                    shader_defs: vec![],
-                    // Make sure this matches the entry point of your shader.
-                    // It can be anything as long as it matches here and in the shader.
-                    entry_point: "fragment".into(),
-                    targets: vec![Some(ColorTargetState {
-                        format: TextureFormat::bevy_default(),
-                        blend: None,
-                        write_mask: ColorWrites::ALL,
-                    })],
-                }),
-                // All of the following properties are not important for this effect so just use the default values.
-                // This struct doesn't have the Default trait implemented because not all fields can have a default value.
-                primitive: PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: MultisampleState::default(),
-                push_constant_ranges: vec![],
-                zero_initialize_workgroup_memory: false,
-            });
+        // Get the shader handles
+        let shaders = OldTvPass::ALL
+            .into_iter()
+            .map(|pass| (pass, world.load_asset(pass.shader_path())))
+            .collect();
 
         Self {
-            layout,
+            view_layout,
+            input_layouts,
             sampler,
-            pipeline_id,
+            linear_sampler,
+            shaders,
+        }
+    }
+}
+
+// Everything about a pass of a view that requires a different pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct OldTvPipelineKey {
+    pass: OldTvPass,
+    // The format of the pass output. For the last pass this is the view's
+    // main texture format, which differs between HDR and non-HDR cameras.
+    texture_format: TextureFormat,
+    // The stages compiled into the shader
+    stages: OldTvStages,
+    // The number of textures read by the pass
+    input_count: usize,
+    curvature: OldTvCurvature,
+    filter: OldTvFilter,
+    mask: OldTvMask,
+    jitter_noise: OldTvNoise,
+    signal: OldTvSignal,
+}
+
+impl OldTvPipelineKey {
+    // Only keeps the settings read by the shader of the pass, so changing the
+    // others doesn't compile the pass again.
+    fn new(
+        pass: OldTvPass,
+        texture_format: TextureFormat,
+        input_count: usize,
+        stages: OldTvStages,
+        settings: &OldTvSettings,
+    ) -> Self {
+        let stages = stages & pass.stages();
+        Self {
+            pass,
+            texture_format,
+            stages,
+            input_count,
+            curvature: if stages.contains(OldTvStages::SCREEN_SHAPE) {
+                settings.curvature
+            } else {
+                default()
+            },
+            filter: if pass == OldTvPass::Pixelate {
+                settings.filter
+            } else {
+                default()
+            },
+            mask: if stages.contains(OldTvStages::PIXEL_COLS) {
+                settings.mask
+            } else {
+                default()
+            },
+            jitter_noise: if stages.contains(OldTvStages::HORIZONTAL_JITTER) {
+                settings.jitter_noise
+            } else {
+                default()
+            },
+            signal: if pass == OldTvPass::Composite {
+                settings.signal
+            } else {
+                default()
+            },
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for OldTvPipeline {
+    type Key = OldTvPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        // Disabled stages are left out of the shader entirely.
+        let shader_defs = [
+            (OldTvStages::SCREEN_SHAPE, "SCREEN_SHAPE"),
+            (OldTvStages::PIXEL_ROWS, "PIXEL_ROWS"),
+            (OldTvStages::PIXEL_COLS, "PIXEL_COLS"),
+            (OldTvStages::BRIGHTNESS, "BRIGHTNESS"),
+            (OldTvStages::SCREEN_EDGES, "SCREEN_EDGES"),
+            (OldTvStages::HALATION, "HALATION"),
+            (OldTvStages::CONVERGENCE, "CONVERGENCE"),
+            (OldTvStages::INTERLACE, "INTERLACE"),
+            (OldTvStages::VERTICAL_ROLL, "VERTICAL_ROLL"),
+            (OldTvStages::HORIZONTAL_JITTER, "HORIZONTAL_JITTER"),
+            (OldTvStages::SNOW, "SNOW"),
+            (OldTvStages::COMPOSITE, "COMPOSITE"),
+            (OldTvStages::VHS, "VHS"),
+            (OldTvStages::GHOSTING, "GHOSTING"),
+            (OldTvStages::VIGNETTE, "VIGNETTE"),
+            (OldTvStages::GLARE, "GLARE"),
+            (OldTvStages::BEZEL, "BEZEL"),
+            (OldTvStages::GAMMA, "GAMMA"),
+            (OldTvStages::PRIMARIES, "PRIMARIES"),
+            (OldTvStages::MONOCHROME, "MONOCHROME"),
+        ]
+        .into_iter()
+        .filter(|(stage, _)| key.stages.contains(*stage))
+        .map(|(_, def)| def.into())
+        .chain((key.pass == OldTvPass::HalationHorizontal).then(|| "HALATION_HORIZONTAL".into()))
+        .chain(match key.curvature {
+            OldTvCurvature::Barrel => None,
+            OldTvCurvature::Spherical => Some("SCREEN_SHAPE_SPHERICAL".into()),
+            OldTvCurvature::Cylindrical => Some("SCREEN_SHAPE_CYLINDRICAL".into()),
+        })
+        .chain(
+            match key.filter {
+                OldTvFilter::Nearest => &[][..],
+                OldTvFilter::Bilinear => &["FILTER_BILINEAR"],
+                OldTvFilter::SharpBilinear => &["FILTER_SHARP_BILINEAR"],
+                OldTvFilter::Cubic => &["FILTER_KERNEL", "FILTER_CUBIC"],
+                OldTvFilter::Lanczos => &["FILTER_KERNEL", "FILTER_LANCZOS"],
+                OldTvFilter::Area => &["FILTER_KERNEL", "FILTER_AREA"],
+            }
+            .iter()
+            .map(|&def| def.into()),
+        )
+        .chain(match key.mask {
+            OldTvMask::None | OldTvMask::ApertureGrille => None,
+            OldTvMask::SlotMask => Some("MASK_SLOT".into()),
+            OldTvMask::ShadowMask => Some("MASK_SHADOW".into()),
+        })
+        .chain(Some(
+            match key.jitter_noise {
+                OldTvNoise::White => "JITTER_WHITE",
+                OldTvNoise::Smooth => "JITTER_SMOOTH",
+                OldTvNoise::Sine => "JITTER_SINE",
+            }
+            .into(),
+        ))
+        .chain(match key.signal {
+            OldTvSignal::Rgb => None,
+            OldTvSignal::Ntsc => Some("NTSC".into()),
+            OldTvSignal::Pal => Some("PAL".into()),
+        })
+        .collect();
+
+        RenderPipelineDescriptor {
+            label: Some(key.pass.label().into()),
+            layout: vec![
+                self.view_layout.clone(),
+                self.input_layouts[key.input_count - 1].clone(),
+            ],
+            // This will setup a fullscreen triangle for the vertex state
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shaders[&key.pass].clone(),
+                shader_defs,
+                // Make sure this matches the entry point of your shader.
+                // It can be anything as long as it matches here and in the shader.
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            // All of the following properties are not important for this effect so just use the default values.
+            // This struct doesn't have the Default trait implemented because not all fields can have a default value.
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+// The pictures persisted by the phosphors of a view in the last two frames.
+//
+// Unlike the other intermediate textures, these must keep their content
+// between frames, so they are owned by the view instead of the texture cache.
+#[derive(Component)]
+struct ViewOldTvHistory {
+    textures: [CachedTexture; 2],
+    // Index of the texture written this frame
+    current: usize,
+}
+
+impl ViewOldTvHistory {
+    // New textures are zeroed, so the first frame blends with a black picture.
+    fn new(render_device: &RenderDevice, size: UVec2) -> Self {
+        let create = || {
+            let texture = render_device.create_texture(&OldTvTexture::History.descriptor(size));
+            let default_view = texture.create_view(&TextureViewDescriptor::default());
+            CachedTexture {
+                texture,
+                default_view,
+            }
+        };
+
+        Self {
+            textures: [create(), create()],
+            current: 0,
+        }
+    }
+
+    fn size(&self) -> UVec2 {
+        let size = self.textures[0].texture.size();
+        UVec2::new(size.width, size.height)
+    }
+}
+
+// Builds the chain of passes of every view with [`OldTvSettings`]. Each pass
+// gets a pipeline specialized for its output format and the stages its shader
+// reads, and each intermediate texture is taken from the texture cache at the
+// size the view needs. Until the pipelines of a new chain are compiled, the
+// view keeps drawing the last chain that was ready.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn prepare_old_tv_passes(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<OldTvPipeline>>,
+    old_tv_pipeline: Res<OldTvPipeline>,
+    mut texture_cache: ResMut<TextureCache>,
+    render_device: Res<RenderDevice>,
+    images: Res<RenderAssets<GpuImage>>,
+    fallback_image: Res<FallbackImageZero>,
+    mut views: Query<(
+        Entity,
+        &ViewTarget,
+        &ExtractedView,
+        &OldTvSettings,
+        Option<&ViewOldTvPasses>,
+        Option<&mut ViewOldTvHistory>,
+    )>,
+) {
+    for (entity, view_target, view, settings, previous, history) in &mut views {
+        let stages = settings.active_stages();
+        let viewport_size = view.viewport.zw();
+        let main_texture_format = view_target.main_texture_format();
+
+        // The lines of the previous field are only kept when they are shown.
+        let weave = stages.contains(OldTvStages::INTERLACE) && settings.interlace_blend > 0.0;
+        let keep_history = stages.contains(OldTvStages::PERSISTENCE) || weave;
+
+        // Each pass reads the picture drawn by the previous one.
+        let mut chain = vec![(
+            OldTvPass::Pixelate,
+            vec![OldTvTexture::Source],
+            Some(OldTvTexture::Pixelated),
+        )];
+        let mut picture = OldTvTexture::Pixelated;
+        if stages.contains(OldTvStages::VHS) {
+            chain.push((OldTvPass::Vhs, vec![picture], Some(OldTvTexture::Vhs)));
+            picture = OldTvTexture::Vhs;
+        }
+        if stages.contains(OldTvStages::COMPOSITE) {
+            chain.push((
+                OldTvPass::Composite,
+                vec![picture],
+                Some(OldTvTexture::Composite),
+            ));
+            picture = OldTvTexture::Composite;
+        }
+        if stages.contains(OldTvStages::PERSISTENCE) {
+            chain.push((
+                OldTvPass::Persistence,
+                vec![picture, OldTvTexture::PreviousHistory],
+                Some(OldTvTexture::History),
+            ));
+            picture = OldTvTexture::History;
+        } else if keep_history {
+            // Without persistence, the received picture is kept as it is.
+            if let Some((_, _, output)) = chain.last_mut() {
+                *output = Some(OldTvTexture::History);
+            }
+            picture = OldTvTexture::History;
+        }
+        if weave {
+            chain.push((
+                OldTvPass::Interlace,
+                vec![picture, OldTvTexture::PreviousHistory],
+                Some(OldTvTexture::Interlaced),
+            ));
+            picture = OldTvTexture::Interlaced;
+        }
+        let mut screen_inputs = vec![OldTvTexture::Source, picture];
+        if stages.contains(OldTvStages::HALATION) {
+            chain.push((
+                OldTvPass::HalationHorizontal,
+                vec![picture],
+                Some(OldTvTexture::HalationHorizontal),
+            ));
+            chain.push((
+                OldTvPass::HalationVertical,
+                vec![OldTvTexture::HalationHorizontal],
+                Some(OldTvTexture::Halation),
+            ));
+            screen_inputs.push(OldTvTexture::Halation);
+        }
+        if settings.bezel.is_some() && stages.contains(OldTvStages::BEZEL) {
+            screen_inputs.push(OldTvTexture::Bezel);
+        }
+        chain.push((OldTvPass::Screen, screen_inputs, None));
+
+        let passes = chain
+            .into_iter()
+            .map(|(pass, inputs, output)| {
+                let texture_format = match output {
+                    Some(_) => OldTvTexture::FORMAT,
+                    None => main_texture_format,
+                };
+                let key =
+                    OldTvPipelineKey::new(pass, texture_format, inputs.len(), stages, settings);
+
+                ViewOldTvPass {
+                    pass,
+                    pipeline_id: pipelines.specialize(&pipeline_cache, &old_tv_pipeline, key),
+                    inputs,
+                    output,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Changing a setting may require new pipelines, and drawing nothing
+        // while they compile would flash the scene without the effect.
+        // Pipelines specialized this frame are only queued, and not known to
+        // `get_render_pipeline` yet.
+        let is_ready = |passes: &[ViewOldTvPass]| {
+            passes.iter().all(|pass| {
+                pipeline_cache
+                    .pipelines()
+                    .nth(pass.pipeline_id.id())
+                    .is_some_and(|pipeline| matches!(pipeline.state, CachedPipelineState::Ok(_)))
+            })
+        };
+        let passes = match previous {
+            Some(previous)
+                if !is_ready(&passes)
+                    && previous.main_texture_format == main_texture_format
+                    && is_ready(&previous.passes) =>
+            {
+                previous.passes.clone()
+            }
+            _ => passes,
+        };
+
+        // The textures are those of the chain being drawn, which may not be
+        // the one the settings ask for.
+        let mut textures = HashMap::default();
+        let used = |texture| {
+            passes
+                .iter()
+                .any(|pass| pass.inputs.contains(&texture) || pass.output == Some(texture))
+        };
+        if used(OldTvTexture::Bezel) {
+            // The picture is drawn without a frame until the image is loaded.
+            let image = settings
+                .bezel
+                .as_ref()
+                .and_then(|bezel| images.get(&bezel.image))
+                .unwrap_or(&fallback_image);
+            textures.insert(
+                OldTvTexture::Bezel,
+                CachedTexture {
+                    texture: image.texture.clone(),
+                    default_view: image.texture_view.clone(),
+                },
+            );
+        }
+        if used(OldTvTexture::History) {
+            let size = OldTvTexture::History.size(viewport_size, settings);
+            let (history, current) = match history {
+                // Swap the textures so the last frame's picture is read
+                Some(mut history) if history.size() == size => {
+                    history.current = 1 - history.current;
+                    (history.textures.clone(), history.current)
+                }
+                // Start over when the view is new or was resized
+                _ => {
+                    let history = ViewOldTvHistory::new(&render_device, size);
+                    let textures = (history.textures.clone(), history.current);
+                    commands.entity(entity).insert(history);
+                    textures
+                }
+            };
+            textures.insert(OldTvTexture::History, history[current].clone());
+            textures.insert(OldTvTexture::PreviousHistory, history[1 - current].clone());
+        } else if history.is_some() {
+            // Re-enabling the stages starts from a black picture
+            commands.entity(entity).remove::<ViewOldTvHistory>();
+        }
+        for texture in passes.iter().filter_map(|pass| pass.output) {
+            textures.entry(texture).or_insert_with(|| {
+                texture_cache.get(
+                    &render_device,
+                    texture.descriptor(texture.size(viewport_size, settings)),
+                )
+            });
+        }
+
+        commands.entity(entity).insert(ViewOldTvPasses {
+            passes,
+            textures,
+            main_texture_format,
+        });
+    }
+}
+
+/// Old TV effect placement
+///
+/// Add this component next to [OldTvSettings] to choose where in the render
+/// graph the effect runs for that camera. Cameras without it keep the
+/// placement the effect always had: [OldTvPlacement::AfterUi] with the "ui"
+/// feature, and otherwise [OldTvPlacement::BeforeTonemapping] for 2D cameras
+/// and [OldTvPlacement::AfterTonemapping] for 3D cameras.
+///
+/// The effect can't run after upscaling: upscaling writes into the final
+/// output texture, which a later pass can't sample.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, ExtractComponent, Reflect)]
+pub enum OldTvPlacement {
+    /// Runs on the HDR scene color, before tonemapping.
+    BeforeTonemapping,
+    /// Runs after tonemapping and the other main post-processing passes, so
+    /// the UI is left undistorted.
+    AfterTonemapping,
+    /// Runs after the UI pass, distorting the UI and text as well.
+    ///
+    /// The effect is only guaranteed to run after the UI with the "ui"
+    /// feature enabled.
+    AfterUi,
+}
+
+impl OldTvPlacement {
+    /// All the placements, in the order they run within a frame.
+    pub const ALL: [Self; 3] = [
+        Self::BeforeTonemapping,
+        Self::AfterTonemapping,
+        Self::AfterUi,
+    ];
+
+    // The placement of 2D cameras without [`OldTvPlacement`]
+    const DEFAULT_2D: Self = if cfg!(feature = "ui") {
+        Self::AfterUi
+    } else {
+        Self::BeforeTonemapping
+    };
+
+    // The placement of 3D cameras without [`OldTvPlacement`]
+    const DEFAULT_3D: Self = if cfg!(feature = "ui") {
+        Self::AfterUi
+    } else {
+        Self::AfterTonemapping
+    };
+}
+
+/// Old TV clock
+///
+/// Drives the time, frame counter and random seed passed to the shader for
+/// animated effects. It is added automatically with [OldTvSettings].
+#[derive(Component, Debug, Default, Clone, Copy, Reflect)]
+pub struct OldTvClock {
+    /// Which time the animations follow
+    pub time: OldTvTime,
+    /// Seed for the random effects
+    ///
+    /// Two cameras with the same seed and clock show the same noise.
+    pub seed: u32,
+    frame: u32,
+}
+
+impl OldTvClock {
+    /// Creates a clock following `time` with the given `seed`.
+    pub fn new(time: OldTvTime, seed: u32) -> Self {
+        Self {
+            time,
+            seed,
+            frame: 0,
+        }
+    }
+
+    /// Number of frames the clock has advanced
+    ///
+    /// Frames where the followed time is paused are not counted.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+}
+
+/// The time followed by an [OldTvClock]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum OldTvTime {
+    /// [`Time<Virtual>`], so the effect pauses and scales with the game.
+    #[default]
+    Virtual,
+    /// [`Time<Real>`], so the effect keeps moving while the game is paused.
+    Real,
+}
+
+fn advance_old_tv_clocks(
+    real_time: Res<Time<Real>>,
+    virtual_time: Res<Time<Virtual>>,
+    mut clocks: Query<&mut OldTvClock>,
+) {
+    for mut clock in &mut clocks {
+        let delta = match clock.time {
+            OldTvTime::Virtual => virtual_time.delta_secs(),
+            OldTvTime::Real => real_time.delta_secs(),
+        };
+        if delta > 0.0 {
+            clock.frame = clock.frame.wrapping_add(1);
         }
     }
 }
 
+// Per-view values that change every frame, bound next to the settings.
+#[derive(Component, Clone, Copy, ShaderType)]
+struct OldTvGlobals {
+    // Physical position (xy) and size (zw) of the camera viewport in pixels
+    viewport: Vec4,
+    // Size of one pixel of the view target in uv units
+    texel_size: Vec2,
+    // Number of emulated columns (x) and rows (y), matching the textures
+    emulated_size: Vec2,
+    // Ratio of physical to logical pixels of the render target
+    scale_factor: f32,
+    // Seconds elapsed on the followed time, wrapped to keep precision
+    time: f32,
+    // Seconds since the previous frame on the followed time
+    delta_time: f32,
+    frame: u32,
+    seed: u32,
+    // Keeps the struct 16 byte aligned for WebGL2
+    _padding_x: u32,
+    _padding_y: u32,
+    _padding_z: u32,
+}
+
+#[allow(clippy::type_complexity)]
+fn extract_old_tv_globals(
+    mut commands: Commands,
+    real_time: Extract<Res<Time<Real>>>,
+    virtual_time: Extract<Res<Time<Virtual>>>,
+    cameras: Extract<Query<(RenderEntity, &Camera, &OldTvClock, &OldTvSettings)>>,
+) {
+    for (entity, camera, clock, settings) in &cameras {
+        // The target is not known until the camera has been updated once
+        let (Some(viewport), Some(target_size), Some(scale_factor)) = (
+            camera.physical_viewport_rect(),
+            camera.physical_target_size(),
+            camera.target_scaling_factor(),
+        ) else {
+            continue;
+        };
+
+        let (time, delta_time) = match clock.time {
+            OldTvTime::Virtual => (
+                virtual_time.elapsed_secs_wrapped(),
+                virtual_time.delta_secs(),
+            ),
+            OldTvTime::Real => (real_time.elapsed_secs_wrapped(), real_time.delta_secs()),
+        };
+
+        commands.entity(entity).insert(OldTvGlobals {
+            viewport: Vec4::new(
+                viewport.min.x as f32,
+                viewport.min.y as f32,
+                viewport.width() as f32,
+                viewport.height() as f32,
+            ),
+            texel_size: 1.0 / target_size.as_vec2(),
+            emulated_size: OldTvTexture::emulated_size(viewport.size(), settings),
+            scale_factor,
+            time,
+            delta_time,
+            frame: clock.frame,
+            seed: clock.seed,
+            _padding_x: 0,
+            _padding_y: 0,
+            _padding_z: 0,
+        });
+    }
+}
+
 /// Old TV settings
 ///
 /// Add this component to effect a camera. These values are passed to the shader
 /// and can be updated dynamically by querying for this component.
-#[derive(Component, Default, Clone, Copy, ExtractComponent, ShaderType, Reflect)]
+#[derive(Component, Debug, Clone, Reflect)]
+#[require(OldTvClock)]
 pub struct OldTvSettings {
-    /// Rounds the corners [0, 1]
+    /// Curvature of the screen
     ///
-    /// The larger the value, the more rounded the screen.
+    /// The larger the value, the more curved the screen. With the barrel
+    /// model this is in [0, 1], with the spherical and cylindrical models it
+    /// is 1 over the radius of the tube, in screen heights.
     pub screen_shape_factor: f32,
+    /// How the screen is curved
+    pub curvature: OldTvCurvature,
+    /// Curvature of each axis, multiplied by
+    /// [OldTvSettings::screen_shape_factor]
+    ///
+    /// The cylindrical model only curves the x axis.
+    pub screen_shape_axes: Vec2,
+    /// Radius of the rounded corners of the screen, in screen widths
+    pub corner_radius: f32,
     /// Controls number of screen rows
     ///
-    /// The columns will be calculated using rows and the derived aspect ratio.
+    /// The columns will be calculated using rows and the viewport aspect ratio.
     pub rows: f32,
+    /// How each emulated pixel is computed from the view
+    ///
+    /// Smoother filters keep scenes that move across the emulated pixels
+    /// from shimmering.
+    pub filter: OldTvFilter,
     /// Screen brightness
     ///
     /// I recommend setting it to 3 or 4 if you do not want create a horror
     /// game.
     pub brightness: f32,
-    /// Screen edge shadow effect size
+    /// Gamma the picture is encoded with in the video signal
+    ///
+    /// The camera of the view sees linear light, which analog video carried
+    /// gamma encoded.
+    pub input_gamma: f32,
+    /// Gamma of the tube, turning the signal back into light
+    ///
+    /// Real tubes are around 2.4. Above [OldTvSettings::input_gamma] the
+    /// picture gets darker and more contrasted.
+    pub output_gamma: f32,
+    /// Light the tube emits for a black signal, relative to white [0, 1)
+    pub black_level: f32,
+    /// Colors of the phosphors
+    pub primaries: OldTvPrimaries,
+    /// Color temperature of the white of the tube, in kelvins [4000, 25000]
+    ///
+    /// Many TVs were set to 9300 K, which looks blue next to the 6504 K of
+    /// the white of sRGB.
+    pub white_point: f32,
+    /// Screen edge shadow effect size, in screen widths
     pub edges_transition_size: f32,
+    /// Phosphor mask pattern
+    ///
+    /// The arrangement of the red, green and blue phosphors on the screen.
+    pub mask: OldTvMask,
+    /// Width of one red, green and blue phosphor triad of the mask
+    pub mask_pitch: OldTvMaskPitch,
     /// RGB channel mask minimum [0, 1]
     ///
     /// Each pixel contains 3 sub-pixels (red, green and blue). This option
     /// allows you to display the color of all channels in any subpixels. I
     /// really recommend play with it.
     pub channels_mask_min: f32,
-    // WebGL2 structs must be 16 byte aligned.
-    // #[cfg(feature = "webgl2")]
-    #[cfg(target_arch = "wasm32")]
-    _webgl2_padding: Vec3,
+    /// Phosphor persistence per color channel [0, 1)
+    ///
+    /// The part of a phosphor's glow left one second after the beam lit it.
+    /// Higher values leave longer trails behind moving objects, 0 disables
+    /// the persistence.
+    pub phosphor_decay: Vec3,
+    /// Phosphor coating the screen
+    ///
+    /// Monochrome phosphors show the luma of the picture in their color and
+    /// replace the phosphor mask.
+    pub phosphor: OldTvPhosphor,
+    /// Halation strength
+    ///
+    /// How much light the bright parts of the picture spread around them
+    /// through the glass, 0 disables the halation.
+    pub halation_strength: f32,
+    /// Halation radius, in emulated pixels
+    pub halation_radius: f32,
+    /// Halation threshold
+    ///
+    /// Only the part of each channel above this value glows.
+    pub halation_threshold: f32,
+    /// Offset of the red beam, in emulated pixels
+    pub red_offset: Vec2,
+    /// Offset of the green beam, in emulated pixels
+    pub green_offset: Vec2,
+    /// Offset of the blue beam, in emulated pixels
+    pub blue_offset: Vec2,
+    /// Radial convergence error, in emulated pixels
+    ///
+    /// Pushes the red beam away from the center of the screen and the blue
+    /// beam toward it, by this distance at the edges and nothing at the center.
+    pub convergence_error: f32,
+    /// Draws every other line each frame
+    ///
+    /// Frames alternate between the even and the odd lines, following the
+    /// [OldTvClock] frame count.
+    pub interlaced: bool,
+    /// Brightness left on the lines of the previous field [0, 1]
+    ///
+    /// 0 only lights the lines of the current field. Higher values keep the
+    /// previous field visible, which shows combing on moving objects.
+    pub interlace_blend: f32,
+    /// Vertical roll position, in roll periods
+    ///
+    /// Scrolls the picture up, wrapping around with a blanking bar between
+    /// the bottom and the top, as when a TV loses its vertical hold. A period
+    /// is the picture plus the blanking bar, so whole numbers show the
+    /// picture in place.
+    pub vertical_roll_offset: f32,
+    /// Vertical roll speed, in roll periods per second
+    ///
+    /// Added to [OldTvSettings::vertical_roll_offset] over the [OldTvClock]
+    /// time. Negative values roll the picture down.
+    pub vertical_roll_speed: f32,
+    /// Height of the blanking bar shown while rolling, in picture heights
+    pub vertical_blanking: f32,
+    /// Horizontal jitter amplitude, in emulated pixels
+    ///
+    /// Shifts each row of the picture sideways by up to this distance, as
+    /// when the horizontal sync of a weak signal is noisy.
+    pub jitter_amplitude: f32,
+    /// How many times per second the jitter changes
+    pub jitter_frequency: f32,
+    /// How the shift varies from row to row
+    pub jitter_noise: OldTvNoise,
+    /// Signal strength [0, 1]
+    ///
+    /// 1 shows a clean picture. Lower values mix more static snow into each
+    /// emulated pixel, down to nothing but snow at 0. The snow changes every
+    /// frame and follows the [OldTvClock] seed.
+    pub signal_strength: f32,
+    /// Video signal carrying the picture to the screen
+    pub signal: OldTvSignal,
+    /// Color subcarrier phase shift from one emulated row to the next, in
+    /// turns
+    ///
+    /// With [OldTvSignal::Ntsc] the default of 0.5 alternates the phase of
+    /// the color artifacts from row to row. PAL uses 0.75.
+    pub subcarrier_line_phase: f32,
+    /// Color subcarrier phase shift from one frame to the next, in turns
+    ///
+    /// Values other than 0 move the color artifacts every frame, which
+    /// makes the dots along sharp edges crawl.
+    pub subcarrier_frame_phase: f32,
+    /// Strength of the composite artifacts [0, 1]
+    ///
+    /// 0 only keeps the limited bandwidth of the signal, 1 adds all the dot
+    /// crawl and rainbow artifacts of luma and chroma mixing together.
+    pub composite_artifacts: f32,
+    /// Phase error of the color decoder, in turns
+    ///
+    /// Shifts the hue with [OldTvSignal::Ntsc]. With [OldTvSignal::Pal] the
+    /// error alternates from row to row, which shows Hanover bars without
+    /// the delay line and lowers the saturation with it.
+    pub subcarrier_phase_error: f32,
+    /// Averages the chroma of each row with the previous one
+    ///
+    /// Only used by [OldTvSignal::Pal], where it cancels the hue errors of
+    /// [OldTvSettings::subcarrier_phase_error] and blurs the colors
+    /// vertically.
+    pub pal_delay_line: bool,
+    /// Plays the picture back from a VHS tape
+    ///
+    /// Blurs the luma and even more the chroma, and adds tracking and head
+    /// switching errors.
+    pub vhs: bool,
+    /// VHS tracking error [0, 1]
+    ///
+    /// Size and strength of the noisy band drifting down the picture, where
+    /// rows are shifted sideways and streaked with white. Animate it to make
+    /// the tracking come and go.
+    pub vhs_tracking_error: f32,
+    /// Height of the rows distorted by VHS head switching at the bottom of
+    /// the picture, in picture heights
+    pub vhs_head_switching: f32,
+    /// How far the VHS chroma lags behind the luma, in emulated pixels
+    pub vhs_chroma_shift: f32,
+    /// Echoes of the picture, as received by an antenna picking up
+    /// reflections of the signal
+    pub ghosts: [OldTvGhost; OldTvSettings::MAX_GHOSTS],
+    /// Vignette strength [0, 1]
+    ///
+    /// How much the corners of the screen are darkened, 0 disables the
+    /// vignette.
+    pub vignette_strength: f32,
+    /// Vignette radius [0, 1)
+    ///
+    /// Distance from the center where the vignette starts, relative to the
+    /// distance to the corners.
+    pub vignette_radius: f32,
+    /// Vignette falloff curve
+    ///
+    /// 1 darkens linearly from the radius to the corners, higher values
+    /// keep more of the picture bright.
+    pub vignette_falloff: f32,
+    /// Position of the center of the glare on the screen, from (0, 0) at the
+    /// top left to (1, 1) at the bottom right
+    pub glare_position: Vec2,
+    /// Size of the glare, in screen heights
+    pub glare_size: f32,
+    /// Brightness of the glare
+    ///
+    /// The glare is the reflection of a room light on the curved glass, 0
+    /// disables it.
+    pub glare_intensity: f32,
+    /// Cabinet drawn around the screen
+    ///
+    /// The image is stretched over the whole view and the picture is fit into
+    /// its screen, `None` shows the picture alone.
+    pub bezel: Option<OldTvBezel>,
+    /// Effect stages to run
+    ///
+    /// Disabled stages are compiled out of the shader.
+    pub stages: OldTvStages,
+}
+
+impl Default for OldTvSettings {
+    fn default() -> Self {
+        Self {
+            screen_shape_factor: 0.2,
+            curvature: OldTvCurvature::Barrel,
+            screen_shape_axes: Vec2::ONE,
+            corner_radius: 0.0,
+            rows: 64.0,
+            filter: OldTvFilter::Nearest,
+            brightness: 3.0,
+            input_gamma: 2.2,
+            output_gamma: 2.2,
+            black_level: 0.0,
+            primaries: OldTvPrimaries::Srgb,
+            white_point: Self::D65_TEMPERATURE,
+            edges_transition_size: 0.025,
+            mask: OldTvMask::ApertureGrille,
+            mask_pitch: OldTvMaskPitch::EmulatedPixels(1.0),
+            channels_mask_min: 0.1,
+            phosphor_decay: Vec3::ZERO,
+            phosphor: OldTvPhosphor::Rgb,
+            halation_strength: 0.0,
+            halation_radius: 4.0,
+            halation_threshold: 0.5,
+            red_offset: Vec2::ZERO,
+            green_offset: Vec2::ZERO,
+            blue_offset: Vec2::ZERO,
+            convergence_error: 0.0,
+            interlaced: false,
+            interlace_blend: 0.5,
+            vertical_roll_offset: 0.0,
+            vertical_roll_speed: 0.0,
+            vertical_blanking: 0.08,
+            jitter_amplitude: 0.0,
+            jitter_frequency: 10.0,
+            jitter_noise: OldTvNoise::Smooth,
+            signal_strength: 1.0,
+            signal: OldTvSignal::Rgb,
+            subcarrier_line_phase: 0.5,
+            subcarrier_frame_phase: 0.5,
+            composite_artifacts: 1.0,
+            subcarrier_phase_error: 0.0,
+            pal_delay_line: true,
+            vhs: false,
+            vhs_tracking_error: 0.1,
+            vhs_head_switching: 0.02,
+            vhs_chroma_shift: 2.0,
+            ghosts: [OldTvGhost::default(); Self::MAX_GHOSTS],
+            vignette_strength: 0.0,
+            vignette_radius: 0.5,
+            vignette_falloff: 2.0,
+            glare_position: Vec2::new(0.3, 0.25),
+            glare_size: 0.15,
+            glare_intensity: 0.0,
+            bezel: None,
+            stages: OldTvStages::all(),
+        }
+    }
+}
+
+impl OldTvSettings {
+    /// Number of [OldTvSettings::ghosts]
+    pub const MAX_GHOSTS: usize = 4;
+    /// The [OldTvSettings::white_point] of sRGB, for which the default
+    /// primaries show the picture unchanged
+    pub const D65_TEMPERATURE: f32 = 6504.0;
+
+    /// Settings for a 480 line NTSC TV
+    pub fn ntsc() -> Self {
+        Self {
+            rows: 480.0,
+            signal: OldTvSignal::Ntsc,
+            subcarrier_line_phase: 0.5,
+            subcarrier_frame_phase: 0.5,
+            ..default()
+        }
+    }
+
+    /// Settings for a 480 line NTSC TV playing a VHS tape
+    pub fn vhs() -> Self {
+        Self {
+            vhs: true,
+            ..Self::ntsc()
+        }
+    }
+
+    /// Settings for a 576 line PAL TV
+    pub fn pal() -> Self {
+        Self {
+            rows: 576.0,
+            signal: OldTvSignal::Pal,
+            subcarrier_line_phase: 0.75,
+            subcarrier_frame_phase: 0.75,
+            ..default()
+        }
+    }
+
+    /// Settings for a 240 line monochrome monitor, with the persistence of
+    /// the phosphor
+    pub fn monochrome(phosphor: OldTvPhosphor) -> Self {
+        Self {
+            rows: 240.0,
+            phosphor,
+            phosphor_decay: Vec3::splat(phosphor.persistence()),
+            ..default()
+        }
+    }
+
+    // The enabled stages, minus the ones whose parameters make them no-ops.
+    fn active_stages(&self) -> OldTvStages {
+        let mut stages = self.stages;
+        if self.screen_shape_factor == 0.0 || self.screen_shape_axes == Vec2::ZERO {
+            stages.remove(OldTvStages::SCREEN_SHAPE);
+        }
+        if self.brightness == 1.0 {
+            stages.remove(OldTvStages::BRIGHTNESS);
+        }
+        if self.mask == OldTvMask::None {
+            stages.remove(OldTvStages::PIXEL_COLS);
+        }
+        // The edges also clip the curved picture outside the screen, where it
+        // would show the edge texels stretched.
+        if self.edges_transition_size <= 0.0
+            && self.corner_radius <= 0.0
+            && !stages.contains(OldTvStages::SCREEN_SHAPE)
+        {
+            stages.remove(OldTvStages::SCREEN_EDGES);
+        }
+        if self.phosphor_decay.max_element() <= 0.0 {
+            stages.remove(OldTvStages::PERSISTENCE);
+        }
+        if self.halation_strength <= 0.0 || self.halation_radius <= 0.0 {
+            stages.remove(OldTvStages::HALATION);
+        }
+        if self.red_offset == Vec2::ZERO
+            && self.green_offset == Vec2::ZERO
+            && self.blue_offset == Vec2::ZERO
+            && self.convergence_error == 0.0
+        {
+            stages.remove(OldTvStages::CONVERGENCE);
+        }
+        if !self.interlaced {
+            stages.remove(OldTvStages::INTERLACE);
+        }
+        if self.vertical_roll_offset == 0.0 && self.vertical_roll_speed == 0.0 {
+            stages.remove(OldTvStages::VERTICAL_ROLL);
+        }
+        if self.jitter_amplitude == 0.0 {
+            stages.remove(OldTvStages::HORIZONTAL_JITTER);
+        }
+        if self.signal_strength >= 1.0 {
+            stages.remove(OldTvStages::SNOW);
+        }
+        if self.signal == OldTvSignal::Rgb {
+            stages.remove(OldTvStages::COMPOSITE);
+        }
+        if !self.vhs {
+            stages.remove(OldTvStages::VHS);
+        }
+        if self.ghosts.iter().all(|ghost| ghost.gain == 0.0) {
+            stages.remove(OldTvStages::GHOSTING);
+        }
+        if self.vignette_strength <= 0.0 {
+            stages.remove(OldTvStages::VIGNETTE);
+        }
+        if self.glare_intensity == 0.0 {
+            stages.remove(OldTvStages::GLARE);
+        }
+        if self.bezel.is_none() {
+            stages.remove(OldTvStages::BEZEL);
+        }
+        if self.input_gamma == self.output_gamma && self.black_level <= 0.0 {
+            stages.remove(OldTvStages::GAMMA);
+        }
+        if self.primaries == OldTvPrimaries::Srgb && self.white_point == Self::D65_TEMPERATURE {
+            stages.remove(OldTvStages::PRIMARIES);
+        }
+        if self.phosphor == OldTvPhosphor::Rgb {
+            stages.remove(OldTvStages::MONOCHROME);
+        }
+        if stages.contains(OldTvStages::MONOCHROME) {
+            // A single phosphor has neither a mask nor primaries.
+            stages.remove(OldTvStages::PIXEL_COLS | OldTvStages::PRIMARIES);
+        }
+        stages
+    }
+
+    // The part of the viewport showing the picture, from (0, 0) at the top left
+    // to (1, 1) at the bottom right: the screen of the bezel if it is drawn.
+    fn screen(&self) -> Rect {
+        match &self.bezel {
+            Some(bezel) if self.active_stages().contains(OldTvStages::BEZEL) => bezel.screen,
+            _ => Rect::new(0.0, 0.0, 1.0, 1.0),
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Old TV effect stages
+    ///
+    /// Used by [OldTvSettings] to pick which stages of the effect run.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+    #[reflect(opaque, Debug, PartialEq, Hash)]
+    pub struct OldTvStages: u32 {
+        /// Curves the picture like the glass of a tube.
+        const SCREEN_SHAPE = 1 << 0;
+        /// Darkens the gaps between the rows of the screen (scanlines).
+        const PIXEL_ROWS = 1 << 1;
+        /// Lays the phosphor mask over the picture, see [OldTvSettings::mask].
+        const PIXEL_COLS = 1 << 2;
+        /// Multiplies the picture by [OldTvSettings::brightness].
+        const BRIGHTNESS = 1 << 3;
+        /// Darkens the edges of the screen.
+        const SCREEN_EDGES = 1 << 4;
+        /// Keeps the phosphors glowing after the picture moves, see
+        /// [OldTvSettings::phosphor_decay].
+        const PERSISTENCE = 1 << 5;
+        /// Spreads the light of the bright parts of the picture, see
+        /// [OldTvSettings::halation_strength].
+        const HALATION = 1 << 6;
+        /// Samples each color channel at a different position, see
+        /// [OldTvSettings::convergence_error].
+        const CONVERGENCE = 1 << 7;
+        /// Alternates between the even and the odd lines, see
+        /// [OldTvSettings::interlaced].
+        const INTERLACE = 1 << 8;
+        /// Scrolls the picture vertically, see
+        /// [OldTvSettings::vertical_roll_speed].
+        const VERTICAL_ROLL = 1 << 9;
+        /// Shifts each row sideways, see [OldTvSettings::jitter_amplitude].
+        const HORIZONTAL_JITTER = 1 << 10;
+        /// Mixes static into the picture, see [OldTvSettings::signal_strength].
+        const SNOW = 1 << 11;
+        /// Encodes and decodes the picture as a composite video signal, see
+        /// [OldTvSettings::signal].
+        const COMPOSITE = 1 << 12;
+        /// Plays the picture back from a tape, see [OldTvSettings::vhs].
+        const VHS = 1 << 13;
+        /// Adds echoes of the picture, see [OldTvSettings::ghosts].
+        const GHOSTING = 1 << 14;
+        /// Darkens the screen away from the center, see
+        /// [OldTvSettings::vignette_strength].
+        const VIGNETTE = 1 << 15;
+        /// Adds a light reflecting on the glass, see
+        /// [OldTvSettings::glare_intensity].
+        const GLARE = 1 << 16;
+        /// Frames the screen with an image, see [OldTvSettings::bezel].
+        const BEZEL = 1 << 17;
+        /// Turns the signal into light with the response of the tube, see
+        /// [OldTvSettings::output_gamma].
+        const GAMMA = 1 << 18;
+        /// Converts the light of the phosphors to the colors of the view, see
+        /// [OldTvSettings::primaries].
+        const PRIMARIES = 1 << 19;
+        /// Shows the picture in the color of a single phosphor, see
+        /// [OldTvSettings::phosphor].
+        const MONOCHROME = 1 << 20;
+    }
+}
+
+impl Default for OldTvStages {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Phosphor mask pattern of an [OldTvSettings]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum OldTvMask {
+    /// No mask, the picture is not split into phosphors.
+    None,
+    /// Continuous vertical stripes, like Trinitron tubes.
+    #[default]
+    ApertureGrille,
+    /// Vertical slots with the columns staggered by half a slot, like most
+    /// consumer TVs.
+    SlotMask,
+    /// Triangles of round dots, like arcade monitors and early TVs.
+    ShadowMask,
+}
+
+/// The unit of [OldTvSettings::mask_pitch]
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum OldTvMaskPitch {
+    /// Pitch in emulated pixels, so the mask scales with
+    /// [OldTvSettings::rows]. A pitch of 1 puts one triad in each column.
+    EmulatedPixels(f32),
+    /// Pitch in physical pixels of the view target, so the mask stays sharp
+    /// whatever the number of rows.
+    PhysicalPixels(f32),
+}
+
+impl Default for OldTvMaskPitch {
+    fn default() -> Self {
+        Self::EmulatedPixels(1.0)
+    }
+}
+
+/// Curvature model of [OldTvSettings::curvature]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum OldTvCurvature {
+    /// Stretches each axis more towards the top and bottom, or the left and
+    /// right, of the screen.
+    #[default]
+    Barrel,
+    /// Projects the picture on a sphere, like most consumer tubes.
+    Spherical,
+    /// Projects the picture on a vertical cylinder, like Trinitron tubes
+    /// which are flat vertically.
+    Cylindrical,
+}
+
+/// Reconstruction filter of [OldTvSettings::filter]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum OldTvFilter {
+    /// Takes a single texel of the view, the sharpest and the most prone to
+    /// shimmering.
+    #[default]
+    Nearest,
+    /// Blends the four texels of the view closest to the center of the
+    /// emulated pixel.
+    Bilinear,
+    /// Like [OldTvFilter::Bilinear], but keeps the texels sharp when the
+    /// emulated pixels are smaller than them.
+    SharpBilinear,
+    /// Catmull-Rom spline over the area of the emulated pixel.
+    Cubic,
+    /// Three lobed Lanczos over the area of the emulated pixel, the sharpest
+    /// of the smooth filters.
+    Lanczos,
+    /// Averages the texels of the view covered by the emulated pixel.
+    Area,
+}
+
+/// Phosphor of [OldTvSettings::phosphor]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum OldTvPhosphor {
+    /// Red, green and blue phosphors laid out by [OldTvSettings::mask].
+    #[default]
+    Rgb,
+    /// Green P1, as on early terminals and oscilloscopes.
+    GreenP1,
+    /// Amber P3, as on terminals of the 1980s.
+    AmberP3,
+    /// White P4, as on black and white TVs.
+    WhiteP4,
+}
+
+impl OldTvPhosphor {
+    /// The [OldTvSettings::phosphor_decay] typical of the phosphor
+    pub fn persistence(self) -> f32 {
+        match self {
+            Self::Rgb => 0.0,
+            Self::GreenP1 => 0.02,
+            Self::AmberP3 => 0.1,
+            Self::WhiteP4 => 0.001,
+        }
+    }
+
+    // The color of the phosphor in linear sRGB, at full brightness.
+    fn color(self) -> Vec3 {
+        match self {
+            Self::Rgb => Vec3::ONE,
+            Self::GreenP1 => Vec3::new(0.03, 1.0, 0.05),
+            Self::AmberP3 => Vec3::new(1.0, 0.42, 0.0),
+            Self::WhiteP4 => Vec3::new(0.85, 0.92, 1.0),
+        }
+    }
+}
+
+/// Phosphor colors of [OldTvSettings::primaries]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+pub enum OldTvPrimaries {
+    /// The primaries of the view, leaving the colors unchanged.
+    #[default]
+    Srgb,
+    /// SMPTE C, the P22 phosphors of North American TVs from the 1980s.
+    SmpteC,
+    /// EBU Tech 3213, the phosphors of European TVs.
+    Ebu,
+    /// The original NTSC primaries of 1953, more saturated than any later
+    /// tube.
+    Ntsc1953,
+    /// A matrix converting the red, green and blue of the tube to CIE XYZ.
+    ///
+    /// The white of the matrix is adapted to [OldTvSettings::white_point].
+    Custom(Mat3),
+}
+
+// The white point of sRGB and most of the primaries.
+const D65: Vec2 = Vec2::new(0.3127, 0.3290);
+
+// Cone response matrix of the Bradford chromatic adaptation transform.
+const BRADFORD: Mat3 = Mat3::from_cols(
+    Vec3::new(0.8951, -0.7502, 0.0389),
+    Vec3::new(0.2664, 1.7135, -0.0685),
+    Vec3::new(-0.1614, 0.0367, 1.0296),
+);
+
+impl OldTvPrimaries {
+    // The matrix converting the red, green and blue of the tube to CIE XYZ.
+    fn rgb_to_xyz(self) -> Mat3 {
+        let [red, green, blue, white] = match self {
+            Self::Srgb => [(0.64, 0.33), (0.30, 0.60), (0.15, 0.06), D65.into()],
+            Self::SmpteC => [(0.630, 0.340), (0.310, 0.595), (0.155, 0.070), D65.into()],
+            Self::Ebu => [(0.64, 0.33), (0.29, 0.60), (0.15, 0.06), D65.into()],
+            // Illuminant C
+            Self::Ntsc1953 => [(0.67, 0.33), (0.21, 0.71), (0.14, 0.08), (0.3101, 0.3162)],
+            Self::Custom(matrix) => return matrix,
+        }
+        .map(|(x, y)| xy_to_xyz(Vec2::new(x, y)));
+        // Each primary is scaled so that the three of them add up to the white.
+        let primaries = Mat3::from_cols(red, green, blue);
+        primaries * Mat3::from_diagonal(primaries.inverse() * white)
+    }
+}
+
+// The CIE XYZ color of a chromaticity, with a luminance of 1.
+fn xy_to_xyz(xy: Vec2) -> Vec3 {
+    Vec3::new(xy.x / xy.y, 1.0, (1.0 - xy.x - xy.y) / xy.y)
+}
+
+// The chromaticity of daylight of the color temperature, in kelvins.
+fn daylight_xy(temperature: f32) -> Vec2 {
+    let t = temperature.clamp(4000.0, 25000.0);
+    let x = if t <= 7000.0 {
+        -4.6070e9 / t.powi(3) + 2.9678e6 / t.powi(2) + 0.09911e3 / t + 0.244063
+    } else {
+        -2.0064e9 / t.powi(3) + 1.9018e6 / t.powi(2) + 0.24748e3 / t + 0.237040
+    };
+    Vec2::new(x, -3.0 * x * x + 2.87 * x - 0.275)
+}
+
+impl OldTvSettings {
+    // Converts the light of the phosphors to linear sRGB. The white of the
+    // primaries is adapted to the white point of the tube, and the result is
+    // shown as is, so a cool white stays cool.
+    fn color_matrix(&self) -> Mat3 {
+        let rgb_to_xyz = self.primaries.rgb_to_xyz();
+        let source_white = BRADFORD * (rgb_to_xyz * Vec3::ONE);
+        let target_white = BRADFORD * xy_to_xyz(daylight_xy(self.white_point));
+        let adaptation =
+            BRADFORD.inverse() * Mat3::from_diagonal(target_white / source_white) * BRADFORD;
+        OldTvPrimaries::Srgb.rgb_to_xyz().inverse() * adaptation * rgb_to_xyz
+    }
+}
+
+/// Noise pattern of [OldTvSettings::jitter_noise]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum OldTvNoise {
+    /// Every row gets its own random value.
+    White,
+    /// Random values blended smoothly across rows and over time.
+    #[default]
+    Smooth,
+    /// A regular wave going down the picture.
+    Sine,
+}
+
+/// Video signal of [OldTvSettings::signal]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum OldTvSignal {
+    /// Separate red, green and blue signals, for a clean picture.
+    #[default]
+    Rgb,
+    /// NTSC composite video. Luma and chroma share a single signal with
+    /// limited bandwidth, which blurs colors and adds dot crawl and rainbows
+    /// along sharp edges.
+    Ntsc,
+    /// PAL composite video. Like [OldTvSignal::Ntsc], but with a wider
+    /// chroma bandwidth and the phase of one chroma component switched on
+    /// every other row.
+    Pal,
+}
+
+/// An echo of the picture, see [OldTvSettings::ghosts]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+pub struct OldTvGhost {
+    /// How far to the right the echo shows, in emulated pixels
+    pub offset: f32,
+    /// Brightness of the echo
+    ///
+    /// Negative values show an inverted echo, 0 disables it.
+    pub gain: f32,
+}
+
+/// A cabinet framing the screen, see [OldTvSettings::bezel]
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct OldTvBezel {
+    /// The cabinet, drawn over the picture using its alpha
+    pub image: Handle<Image>,
+    /// Where the screen shows in the image, from (0, 0) at the top left to
+    /// (1, 1) at the bottom right
+    ///
+    /// Divide a rectangle in pixels by the size of the image to get it.
+    pub screen: Rect,
+}
+
+// Copies the settings into the render world, where they specialize the
+// pipeline, along with the uniform passed to the shader.
+fn extract_old_tv_settings(
+    mut commands: Commands,
+    cameras: Extract<Query<(RenderEntity, &OldTvSettings)>>,
+) {
+    for (entity, settings) in &cameras {
+        commands
+            .entity(entity)
+            .insert((settings.clone(), OldTvUniform::from(settings)));
+    }
+}
+
+// The part of [`OldTvSettings`] passed to the shader.
+#[derive(Component, Clone, Copy, ShaderType)]
+struct OldTvUniform {
+    screen_shape_factor: f32,
+    brightness: f32,
+    edges_transition_size: f32,
+    screen_shape_axes: Vec2,
+    corner_radius: f32,
+    channels_mask_min: f32,
+    // Being a vec3, this also keeps the struct 16 byte aligned as WebGL2
+    // requires.
+    phosphor_decay: Vec3,
+    halation_strength: f32,
+    halation_radius: f32,
+    halation_threshold: f32,
+    red_offset: Vec2,
+    green_offset: Vec2,
+    blue_offset: Vec2,
+    convergence_error: f32,
+    mask_pitch: f32,
+    // 1 when `mask_pitch` is in physical pixels, 0 when in emulated pixels
+    mask_pitch_physical: u32,
+    interlace_blend: f32,
+    vertical_roll_offset: f32,
+    vertical_roll_speed: f32,
+    vertical_blanking: f32,
+    jitter_amplitude: f32,
+    jitter_frequency: f32,
+    signal_strength: f32,
+    subcarrier_line_phase: f32,
+    subcarrier_frame_phase: f32,
+    composite_artifacts: f32,
+    subcarrier_phase_error: f32,
+    pal_delay_line: u32,
+    vhs_tracking_error: f32,
+    vhs_head_switching: f32,
+    vhs_chroma_shift: f32,
+    ghost_offsets: Vec4,
+    ghost_gains: Vec4,
+    vignette_strength: f32,
+    vignette_radius: f32,
+    vignette_falloff: f32,
+    glare_position: Vec2,
+    glare_size: f32,
+    glare_intensity: f32,
+    // The screen of the bezel, with the position in xy and the size in zw
+    bezel_screen: Vec4,
+    input_gamma: f32,
+    output_gamma: f32,
+    black_level: f32,
+    // Converts the light of the phosphors to linear sRGB
+    color_matrix: Mat3,
+    phosphor_color: Vec3,
+}
+
+impl From<&OldTvSettings> for OldTvUniform {
+    fn from(settings: &OldTvSettings) -> Self {
+        Self {
+            screen_shape_factor: settings.screen_shape_factor,
+            brightness: settings.brightness,
+            edges_transition_size: settings.edges_transition_size,
+            screen_shape_axes: settings.screen_shape_axes,
+            corner_radius: settings.corner_radius,
+            channels_mask_min: settings.channels_mask_min,
+            phosphor_decay: settings.phosphor_decay,
+            halation_strength: settings.halation_strength,
+            halation_radius: settings.halation_radius,
+            halation_threshold: settings.halation_threshold,
+            red_offset: settings.red_offset,
+            green_offset: settings.green_offset,
+            blue_offset: settings.blue_offset,
+            convergence_error: settings.convergence_error,
+            mask_pitch: match settings.mask_pitch {
+                OldTvMaskPitch::EmulatedPixels(pitch) | OldTvMaskPitch::PhysicalPixels(pitch) => {
+                    pitch
+                }
+            },
+            mask_pitch_physical: matches!(settings.mask_pitch, OldTvMaskPitch::PhysicalPixels(_))
+                as u32,
+            interlace_blend: settings.interlace_blend,
+            vertical_roll_offset: settings.vertical_roll_offset,
+            vertical_roll_speed: settings.vertical_roll_speed,
+            vertical_blanking: settings.vertical_blanking,
+            jitter_amplitude: settings.jitter_amplitude,
+            jitter_frequency: settings.jitter_frequency,
+            signal_strength: settings.signal_strength,
+            subcarrier_line_phase: settings.subcarrier_line_phase,
+            subcarrier_frame_phase: settings.subcarrier_frame_phase,
+            composite_artifacts: settings.composite_artifacts,
+            subcarrier_phase_error: settings.subcarrier_phase_error,
+            pal_delay_line: settings.pal_delay_line as u32,
+            vhs_tracking_error: settings.vhs_tracking_error,
+            vhs_head_switching: settings.vhs_head_switching,
+            vhs_chroma_shift: settings.vhs_chroma_shift,
+            ghost_offsets: Vec4::from_array(settings.ghosts.map(|ghost| ghost.offset)),
+            ghost_gains: Vec4::from_array(settings.ghosts.map(|ghost| ghost.gain)),
+            vignette_strength: settings.vignette_strength,
+            vignette_radius: settings.vignette_radius,
+            vignette_falloff: settings.vignette_falloff,
+            glare_position: settings.glare_position,
+            glare_size: settings.glare_size,
+            glare_intensity: settings.glare_intensity,
+            bezel_screen: {
+                let screen = settings.screen();
+                Vec4::new(screen.min.x, screen.min.y, screen.width(), screen.height())
+            },
+            input_gamma: settings.input_gamma,
+            output_gamma: settings.output_gamma,
+            black_level: settings.black_level,
+            color_matrix: settings.color_matrix(),
+            phosphor_color: settings.phosphor.color(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_matrix(primaries: OldTvPrimaries, white_point: f32) -> Mat3 {
+        OldTvSettings {
+            primaries,
+            white_point,
+            ..default()
+        }
+        .color_matrix()
+    }
+
+    #[test]
+    fn daylight_at_d65_temperature_is_d65() {
+        let xy = daylight_xy(OldTvSettings::D65_TEMPERATURE);
+        assert!(xy.abs_diff_eq(D65, 5e-4), "{xy}");
+    }
+
+    #[test]
+    fn srgb_at_d65_is_identity() {
+        let matrix = color_matrix(OldTvPrimaries::Srgb, OldTvSettings::D65_TEMPERATURE);
+        assert!(matrix.abs_diff_eq(Mat3::IDENTITY, 1e-3), "{matrix}");
+    }
+
+    #[test]
+    fn d65_primaries_keep_white() {
+        for primaries in [OldTvPrimaries::Ebu, OldTvPrimaries::SmpteC] {
+            let white = color_matrix(primaries, OldTvSettings::D65_TEMPERATURE) * Vec3::ONE;
+            assert!(white.abs_diff_eq(Vec3::ONE, 1e-3), "{primaries:?}: {white}");
+        }
+    }
+
+    #[test]
+    fn cool_white_point_is_blue() {
+        let white = color_matrix(OldTvPrimaries::Srgb, 9300.0) * Vec3::ONE;
+        assert!(white.z > white.x, "{white}");
+    }
 }
@@ -1,32 +1,160 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_old_tv_shader::common::{
+    texture_sampler, linear_sampler, settings, globals, aspect_ratio, emulated_size, random,
+    encode_gamma,
+}
 
-@group(0) @binding(0) var screen_texture: texture_2d<f32>;
-@group(0) @binding(1) var texture_sampler: sampler;
-struct OldTvSettings {
-    screen_shape_factor: f32,
-    rows: f32,
-    brightness: f32,
-    edges_transition_size: f32,
-    channels_mask_min: f32,
-#ifdef SIXTEEN_BYTE_ALIGNMENT
-    // WebGL2 structs must be 16 byte aligned.
-    _webgl2_padding: vec3<f32>
+// The view target before the effect
+@group(1) @binding(0) var source_texture: texture_2d<f32>;
+// One texel per emulated pixel, written by the pixelate pass
+@group(1) @binding(1) var pixelated_texture: texture_2d<f32>;
+#ifdef HALATION
+// The glow around the bright parts of the picture, written by the halation passes
+@group(1) @binding(2) var halation_texture: texture_2d<f32>;
+#endif
+#ifdef BEZEL
+// The cabinet around the screen, after the glow if there is one
+#ifdef HALATION
+@group(1) @binding(3) var bezel_texture: texture_2d<f32>;
+#else
+@group(1) @binding(2) var bezel_texture: texture_2d<f32>;
+#endif
 #endif
-}
-@group(0) @binding(2) var<uniform> settings: OldTvSettings;
 
-fn apply_screen_shape(uv_: vec2<f32>, factor: f32) -> vec2<f32> {
+fn apply_screen_shape(uv_: vec2<f32>, ratio: f32) -> vec2<f32> {
+    let curvature = settings.screen_shape_factor * settings.screen_shape_axes;
+#ifdef SCREEN_SHAPE_SPHERICAL
+    // The screen is seen from the front, so a point at a distance r from the
+    // center shows the picture at the length of the arc above it.
+    let position = (uv_ - 0.5) * vec2(ratio, 1.0);
+    let angle = min(length(position) * curvature, vec2(1.0));
+    let arc = select(asin(angle) / angle, vec2(1.0), angle < vec2(1e-4));
+    return position * arc / vec2(ratio, 1.0) + 0.5;
+#else ifdef SCREEN_SHAPE_CYLINDRICAL
+    // Like the sphere, with the vertical axis left flat.
+    let x = (uv_.x - 0.5) * ratio;
+    let angle = min(abs(x) * curvature.x, 1.0);
+    let arc = select(asin(angle) / angle, 1.0, angle < 1e-4);
+    return vec2(x * arc / ratio + 0.5, uv_.y);
+#else
     var uv = uv_ - vec2(0.5, 0.5);
-    uv = uv * (uv.yx * uv.yx * factor + 1.0);
+    uv = uv * (uv.yx * uv.yx * curvature + 1.0);
     return uv + vec2(0.5, 0.5);
+#endif
+}
+
+// Scrolls the picture up, with a blanking bar between two copies of it. The
+// returned uv is below the picture inside the bar.
+fn apply_vertical_roll(uv: vec2<f32>) -> vec2<f32> {
+    let period = 1.0 + max(settings.vertical_blanking, 0.0);
+    let roll = settings.vertical_roll_offset + settings.vertical_roll_speed * globals.time;
+    let y = uv.y + roll * period;
+    return vec2(uv.x, y - floor(y / period) * period);
+}
+
+#ifdef HORIZONTAL_JITTER
+// Noise cells or sine periods per picture height, for the jitter patterns
+// that are coherent across rows
+const JITTER_SCALE: f32 = 8.0;
+
+// Returns the jitter of the row in [-1, 1].
+fn jitter_noise(row: f32, rows: f32) -> f32 {
+    let t = globals.time * settings.jitter_frequency;
+#ifdef JITTER_WHITE
+    return random(u32(row), u32(floor(t)), 0u) * 2.0 - 1.0;
+#else ifdef JITTER_SINE
+    return sin(6.2831855 * (row / rows * JITTER_SCALE - t));
+#else
+    // Value noise, interpolated between cells across rows and over time
+    let p = vec2(row / rows * JITTER_SCALE, t);
+    let cell = vec2<u32>(floor(p));
+    let f = smoothstep(vec2(0.0), vec2(1.0), fract(p));
+    let a = random(cell.x, cell.y, 0u);
+    let b = random(cell.x + 1u, cell.y, 0u);
+    let c = random(cell.x, cell.y + 1u, 0u);
+    let d = random(cell.x + 1u, cell.y + 1u, 0u);
+    return mix(mix(a, b, f.x), mix(c, d, f.x), f.y) * 2.0 - 1.0;
+#endif
 }
 
+// Shifts the picture sideways by the same amount along each emulated row.
+fn apply_horizontal_jitter(uv: vec2<f32>, size: vec2<f32>) -> vec2<f32> {
+    let row = pixelate(uv, size).y;
+    let shift = jitter_noise(row, size.y) * settings.jitter_amplitude / size.x;
+    return vec2(uv.x + shift, uv.y);
+}
+#endif
+
+// Returns the emulated pixel under the uv.
 fn pixelate(uv: vec2<f32>, size: vec2<f32>) -> vec2<f32> {
-    return floor(uv * size) / size;
+    return floor(uv * size);
+}
+
+fn get_pixel_color(pixel: vec2<f32>) -> vec4<f32> {
+    let size = vec2<f32>(textureDimensions(pixelated_texture));
+    return textureSampleLevel(pixelated_texture, texture_sampler, (pixel + 0.5) / size, 0.0);
+}
+
+// Returns the color the beams draw at the uv. Misconverged beams land each
+// channel on a different emulated pixel.
+fn get_beam_color(uv: vec2<f32>, size: vec2<f32>) -> vec4<f32> {
+#ifdef CONVERGENCE
+    // Red and blue drift apart radially, the most in the corners.
+    let radial = (uv - 0.5) * 2.0 * settings.convergence_error;
+    let red = get_pixel_color(pixelate(uv + (settings.red_offset + radial) / size, size));
+    let green = get_pixel_color(pixelate(uv + settings.green_offset / size, size));
+    let blue = get_pixel_color(pixelate(uv + (settings.blue_offset - radial) / size, size));
+    return vec4(red.r, green.g, blue.b, green.a);
+#else
+    return get_pixel_color(pixelate(uv, size));
+#endif
+}
+
+// Must match `OldTvSettings::MAX_GHOSTS`.
+const MAX_GHOSTS: i32 = 4;
+
+// Returns the color of the emulated pixels under the uv, with the echoes of
+// the signal received after it. They come later along the line, so they show
+// to the right.
+fn get_texture_color(uv: vec2<f32>, size: vec2<f32>) -> vec4<f32> {
+    var color = get_beam_color(uv, size);
+#ifdef GHOSTING
+    for (var i = 0; i < MAX_GHOSTS; i++) {
+        let offset = vec2(settings.ghost_offsets[i] / size.x, 0.0);
+        let ghost = get_beam_color(uv - offset, size);
+        color = vec4(color.rgb + ghost.rgb * settings.ghost_gains[i], color.a);
+    }
+#endif
+    return color;
+}
+
+// Turns the signal into light like the tube, following BT.1886: the black level
+// lifts the signal before the tube's gamma.
+fn apply_crt_response(color: vec4<f32>) -> vec4<f32> {
+    let gamma = settings.output_gamma;
+    let lift = pow(saturate(settings.black_level), 1.0 / gamma);
+    let signal = encode_gamma(color.rgb);
+    let light = pow(max(signal * (1.0 - lift) + lift, vec3(0.0)), vec3(gamma));
+    return vec4(light, color.a);
 }
 
-fn get_texture_color(uv: vec2<f32>) -> vec4<f32> {
-    return textureSample(screen_texture, texture_sampler, uv);
+#ifdef HALATION
+// Adds the light spread through the glass. It is smooth, so unlike the emulated
+// pixels it is filtered between texels.
+fn apply_halation(color: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {
+    let halation = textureSampleLevel(halation_texture, linear_sampler, uv, 0.0).rgb;
+    return vec4(color.rgb + halation * settings.halation_strength, color.a);
+}
+#endif
+
+// Dims the lines of the field the beam doesn't draw this frame.
+fn apply_interlace(color: vec4<f32>, uv: vec2<f32>, rows: f32) -> vec4<f32> {
+    let field = globals.frame % 2u;
+    let line = u32(uv.y * rows);
+    if line % 2u == field {
+        return color;
+    }
+    return color * vec4(vec3(settings.interlace_blend), 1.0);
 }
 
 fn apply_pixel_rows(color: vec4<f32>, uv: vec2<f32>, rows: f32) -> vec4<f32> {
@@ -35,11 +163,48 @@ fn apply_pixel_rows(color: vec4<f32>, uv: vec2<f32>, rows: f32) -> vec4<f32> {
     return mix(color, vec4<f32>(0., 0., 0., 1.), f);
 }
 
+// Returns the position in the phosphor mask, in triads. Triads are as tall as
+// they are wide.
+fn mask_position(uv: vec2<f32>, cols: f32) -> vec2<f32> {
+    var triads = cols / settings.mask_pitch;
+    if settings.mask_pitch_physical == 1u {
+        triads = globals.viewport.z * settings.bezel_screen.z / settings.mask_pitch;
+    }
+    return uv * vec2(triads, triads / aspect_ratio());
+}
+
+// A single phosphor covers the screen, lit by the luma of the picture.
+fn apply_monochrome(color: vec4<f32>) -> vec4<f32> {
+    let luma = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+    return vec4(luma * settings.phosphor_color, color.a);
+}
+
 fn apply_pixel_cols(color: vec4<f32>, uv: vec2<f32>, cols: f32) -> vec4<f32> {
-    var f = abs(fract(uv.x * cols * 3.) - 0.5) * 2.;
+    let position = mask_position(uv, cols);
+
+#ifdef MASK_SLOT
+    // Every other column of slots is shifted by half a slot.
+    let slot_y = position.y + 0.5 * (floor(position.x) % 2.0);
+    let gap_x = abs(fract(position.x * 3.) - 0.5) * 2.;
+    let gap_y = abs(fract(slot_y) - 0.5) * 2.;
+    let f = max(gap_x * gap_x, pow(gap_y, 8.0));
+    let channel = u32(fract(position.x) * 3.0);
+#else ifdef MASK_SHADOW
+    // Rows of round dots, every other row shifted by one and a half dots so
+    // the red, green and blue dots form triangles.
+    let row_height = sqrt(3.0) / 2.0;
+    let dots = position * vec2(3.0, 3.0 / row_height);
+    let dot_x = dots.x + 1.5 * (floor(dots.y) % 2.0);
+    let offset = (fract(vec2(dot_x, dots.y)) - 0.5) * vec2(1.0, row_height);
+    var f = min(length(offset) * 2., 1.);
+    f = f * f;
+    let channel = u32(dot_x) % 3u;
+#else
+    var f = abs(fract(position.x * 3.) - 0.5) * 2.;
     f = f * f;
+    let channel = u32(fract(position.x) * 3.0);
+#endif
 
-    let channel = u32(fract(uv.x * cols) * 3.0);
     let channels_mask_min = settings.channels_mask_min;
 
     var channel_mask = vec4(1.0, channels_mask_min, channels_mask_min, 1.0);
@@ -52,48 +217,145 @@ fn apply_pixel_cols(color: vec4<f32>, uv: vec2<f32>, cols: f32) -> vec4<f32> {
     return mix(color * channel_mask, vec4<f32>(0., 0., 0., 1.), f);
 }
 
-// Get the aspect ratio if all you have is the uv coordinates.
-fn aspect_ratio(uv: vec2<f32>) -> f32 {
-    return dpdy(uv.y) / dpdx(uv.x);
-}
-
 fn apply_screen_edges(color: vec4<f32>, uv: vec2<f32>, ratio: f32) -> vec4<f32> {
-    let edges_transition_size = settings.edges_transition_size;
-    let edge_x = min(uv.x / edges_transition_size, (1.0 - uv.x) / edges_transition_size);
-    let edge_y = min(uv.y / edges_transition_size / ratio, (1.0 - uv.y) / edges_transition_size / ratio);
+    // Distance to the rounded rectangle of the screen, in screen widths,
+    // negative inside.
+    let half_size = vec2(0.5, 0.5 / ratio);
+    let radius = clamp(settings.corner_radius, 0.0, min(half_size.x, half_size.y));
+    let corner = abs((uv - 0.5) * vec2(1.0, 1.0 / ratio)) - half_size + radius;
+    let distance = length(max(corner, vec2(0.0))) + min(max(corner.x, corner.y), 0.0) - radius;
 
-    let edge = vec2(
-        max(edge_x, 0.0),
-        max(edge_y, 0.0),
-    );
-    var f = min(edge.x, edge.y);
-    f = min(f, 1.0);
+    let f = saturate(-distance / max(settings.edges_transition_size, 1e-5));
 
     return vec4(color.xyz * f, 1.0);
 } 
 
+// Darkens the picture away from the center, from the radius to the corners.
+fn apply_vignette(color: vec4<f32>, uv: vec2<f32>, ratio: f32) -> vec4<f32> {
+    let corner = vec2(ratio, 1.0);
+    let distance = length((uv - 0.5) * 2.0 * corner) / length(corner);
+    let radius = min(settings.vignette_radius, 0.999);
+    let f = pow(saturate((distance - radius) / (1.0 - radius)), settings.vignette_falloff);
+    return vec4(color.rgb * (1.0 - saturate(settings.vignette_strength) * f), color.a);
+}
+
+// Adds the reflection of a light on the glass. It is placed on the same uv as
+// the picture, so it follows the curve of the screen.
+fn apply_glare(color: vec4<f32>, uv: vec2<f32>, ratio: f32) -> vec4<f32> {
+    if any(uv < vec2(0.0)) || any(uv > vec2(1.0)) {
+        return color;
+    }
+    let offset = (uv - settings.glare_position) * vec2(ratio, 1.0);
+    let sigma = max(settings.glare_size, 1e-4) * 0.5;
+    let glare = settings.glare_intensity * exp(-dot(offset, offset) / (2.0 * sigma * sigma));
+    return vec4(color.rgb + glare, color.a);
+}
+
 fn apply_brightness(color: vec4<f32>) -> vec4<f32> {
     return color * vec4(vec3(settings.brightness), 1.0);
 }
 
+#ifdef BEZEL
+fn apply_bezel(color: vec4<f32>, viewport_uv: vec2<f32>, screen_uv: vec2<f32>) -> vec4<f32> {
+    var picture = color;
+    // Nothing shows outside the screen, even where the image is transparent.
+    if any(screen_uv < vec2(0.0)) || any(screen_uv > vec2(1.0)) {
+        picture = vec4(0.0, 0.0, 0.0, 1.0);
+    }
+    let bezel = textureSampleLevel(bezel_texture, linear_sampler, viewport_uv, 0.0);
+    return vec4(mix(picture.rgb, bezel.rgb, bezel.a), picture.a);
+}
+#endif
+
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
-    // let ratio = 3.0;
-    let ratio = aspect_ratio(in.uv);
-    let uv = apply_screen_shape(in.uv, settings.screen_shape_factor);
-    let rows = settings.rows;
-    
-    let cols = rows * ratio;
+    // The view target may be shared with other cameras, so only the pixels
+    // inside this camera's viewport get the effect.
+    let viewport_uv = (in.position.xy - globals.viewport.xy) / globals.viewport.zw;
+    if any(viewport_uv < vec2(0.0)) || any(viewport_uv > vec2(1.0)) {
+        return textureSampleLevel(source_texture, texture_sampler, in.uv, 0.0);
+    }
+
+#ifdef BEZEL
+    // The picture is fit into the screen of the bezel.
+    let screen_uv = (viewport_uv - settings.bezel_screen.xy) / settings.bezel_screen.zw;
+#else
+    let screen_uv = viewport_uv;
+#endif
+    let ratio = aspect_ratio();
+#ifdef SCREEN_SHAPE
+    let uv = apply_screen_shape(screen_uv, ratio);
+#else
+    let uv = screen_uv;
+#endif
+    let size = emulated_size();
+    let rows = size.y;
+    let cols = size.x;
 
-    let texture_uv = pixelate(uv, vec2(cols, rows));
+    // The lines drawn by the beam move with the picture, the phosphors don't.
+#ifdef VERTICAL_ROLL
+    var picture_uv = apply_vertical_roll(uv);
+#else
+    var picture_uv = uv;
+#endif
+#ifdef HORIZONTAL_JITTER
+    picture_uv = apply_horizontal_jitter(picture_uv, size);
+#endif
+
+    var color = get_texture_color(picture_uv, size);
 
-    var color = get_texture_color(texture_uv);
+    // From here on the colors are the light of the screen, so the mask and
+    // the rows darken them linearly.
+#ifdef GAMMA
+    color = apply_crt_response(color);
+#endif
+#ifdef HALATION
+    color = apply_halation(color, picture_uv);
+#endif
+#ifdef VERTICAL_ROLL
+    // The beam is off while it goes back to the top of the picture.
+    if picture_uv.y > 1.0 {
+        color = vec4(0.0, 0.0, 0.0, 1.0);
+    }
+#endif
+#ifdef HORIZONTAL_JITTER
+    // Nor is anything drawn past the sides of the picture.
+    if picture_uv.x < 0.0 || picture_uv.x > 1.0 {
+        color = vec4(0.0, 0.0, 0.0, 1.0);
+    }
+#endif
 
-    color = apply_pixel_rows(color, uv, rows);
+#ifdef INTERLACE
+    color = apply_interlace(color, picture_uv, rows);
+#endif
+#ifdef PIXEL_ROWS
+    color = apply_pixel_rows(color, picture_uv, rows);
+#endif
+#ifdef MONOCHROME
+    color = apply_monochrome(color);
+#else ifdef PIXEL_COLS
     color = apply_pixel_cols(color, uv, cols);
+#endif
+#ifdef PRIMARIES
+    // Each phosphor lights up with its own color.
+    color = vec4(settings.color_matrix * color.rgb, color.a);
+#endif
 
+#ifdef BRIGHTNESS
     color = apply_brightness(color);
+#endif
+#ifdef SCREEN_EDGES
     color = apply_screen_edges(color, uv, ratio);
+#endif
+#ifdef VIGNETTE
+    color = apply_vignette(color, uv, ratio);
+#endif
+#ifdef GLARE
+    color = apply_glare(color, uv, ratio);
+#endif
+#ifdef BEZEL
+    color = apply_bezel(color, viewport_uv, screen_uv);
+#endif
 
     return color;
     // return vec4(ratio/ 2, 0, 0, 1);
@@ -0,0 +1,127 @@
+#define_import_path bevy_old_tv_shader::common
+
+// Bindings shared by all the passes of the effect.
+@group(0) @binding(0) var texture_sampler: sampler;
+struct OldTvSettings {
+    screen_shape_factor: f32,
+    brightness: f32,
+    edges_transition_size: f32,
+    screen_shape_axes: vec2<f32>,
+    corner_radius: f32,
+    channels_mask_min: f32,
+    phosphor_decay: vec3<f32>,
+    halation_strength: f32,
+    halation_radius: f32,
+    halation_threshold: f32,
+    red_offset: vec2<f32>,
+    green_offset: vec2<f32>,
+    blue_offset: vec2<f32>,
+    convergence_error: f32,
+    mask_pitch: f32,
+    // 1 when `mask_pitch` is in physical pixels, 0 when in emulated pixels
+    mask_pitch_physical: u32,
+    interlace_blend: f32,
+    vertical_roll_offset: f32,
+    vertical_roll_speed: f32,
+    vertical_blanking: f32,
+    jitter_amplitude: f32,
+    jitter_frequency: f32,
+    signal_strength: f32,
+    subcarrier_line_phase: f32,
+    subcarrier_frame_phase: f32,
+    composite_artifacts: f32,
+    subcarrier_phase_error: f32,
+    // 1 to average the chroma of PAL rows with the previous one
+    pal_delay_line: u32,
+    vhs_tracking_error: f32,
+    vhs_head_switching: f32,
+    vhs_chroma_shift: f32,
+    ghost_offsets: vec4<f32>,
+    ghost_gains: vec4<f32>,
+    vignette_strength: f32,
+    vignette_radius: f32,
+    vignette_falloff: f32,
+    glare_position: vec2<f32>,
+    glare_size: f32,
+    glare_intensity: f32,
+    // The screen of the bezel, with the position in xy and the size in zw
+    bezel_screen: vec4<f32>,
+    input_gamma: f32,
+    output_gamma: f32,
+    black_level: f32,
+    // Converts the light of the phosphors to linear sRGB
+    color_matrix: mat3x3<f32>,
+    phosphor_color: vec3<f32>,
+}
+@group(0) @binding(1) var<uniform> settings: OldTvSettings;
+struct OldTvGlobals {
+    // Physical position (xy) and size (zw) of the camera viewport in pixels
+    viewport: vec4<f32>,
+    // Size of one pixel of the view target in uv units
+    texel_size: vec2<f32>,
+    // Number of emulated columns (x) and rows (y), matching the textures
+    emulated_size: vec2<f32>,
+    scale_factor: f32,
+    // Seconds elapsed, wrapped to keep precision
+    time: f32,
+    delta_time: f32,
+    frame: u32,
+    seed: u32,
+    _padding_x: u32,
+    _padding_y: u32,
+    _padding_z: u32,
+}
+@group(0) @binding(2) var<uniform> globals: OldTvGlobals;
+// Blends neighboring texels, unlike `texture_sampler`
+@group(0) @binding(3) var linear_sampler: sampler;
+
+// Converts a uv within the camera viewport to a uv within the whole view target.
+fn viewport_to_target_uv(uv: vec2<f32>) -> vec2<f32> {
+    return (globals.viewport.xy + uv * globals.viewport.zw) * globals.texel_size;
+}
+
+const RGB_TO_YIQ: mat3x3<f32> = mat3x3<f32>(
+    vec3(0.299, 0.5959, 0.2115),
+    vec3(0.587, -0.2746, -0.5227),
+    vec3(0.114, -0.3213, 0.3112),
+);
+const YIQ_TO_RGB: mat3x3<f32> = mat3x3<f32>(
+    vec3(1.0, 1.0, 1.0),
+    vec3(0.956, -0.272, -1.106),
+    vec3(0.619, -0.647, 1.703),
+);
+
+// Analog video carries gamma encoded colors, while the passes work on linear
+// colors.
+fn encode_gamma(linear: vec3<f32>) -> vec3<f32> {
+    return pow(max(linear, vec3(0.0)), vec3(1.0 / settings.input_gamma));
+}
+
+fn decode_gamma(encoded: vec3<f32>) -> vec3<f32> {
+    return pow(max(encoded, vec3(0.0)), vec3(settings.input_gamma));
+}
+
+// PCG hash, from "Hash Functions for GPU Rendering" (Jarzynski and Olano, 2020).
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Returns a random number in [0, 1) for the three values, different for each
+// clock seed.
+fn random(x: u32, y: u32, z: u32) -> f32 {
+    return f32(hash(x ^ hash(y ^ hash(z ^ globals.seed))) >> 8u) / 16777216.0;
+}
+
+// Width over height of the screen, which is the screen of the bezel if there
+// is one.
+fn aspect_ratio() -> f32 {
+    let size = globals.viewport.zw * settings.bezel_screen.zw;
+    return size.x / size.y;
+}
+
+// Number of emulated columns (x) and rows (y).
+fn emulated_size() -> vec2<f32> {
+    return globals.emulated_size;
+}
@@ -0,0 +1,19 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_old_tv_shader::common::{texture_sampler, settings, globals}
+
+// The emulated pixels of this frame
+@group(1) @binding(0) var picture_texture: texture_2d<f32>;
+// The picture with the phosphor glow of the previous frame
+@group(1) @binding(1) var history_texture: texture_2d<f32>;
+
+// A phosphor lit again shines at least as bright as what is left of its glow.
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(picture_texture, texture_sampler, in.uv, 0.0);
+    let previous = textureSampleLevel(history_texture, texture_sampler, in.uv, 0.0);
+
+    // The decay is given per second, so the trails don't depend on the frame rate.
+    let glow = previous.rgb * pow(settings.phosphor_decay, vec3(globals.delta_time));
+
+    return vec4(max(color.rgb, glow), color.a);
+}
@@ -0,0 +1,126 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_old_tv_shader::common::{
+    texture_sampler, linear_sampler, settings, globals, viewport_to_target_uv, emulated_size,
+    random,
+}
+
+@group(1) @binding(0) var source_texture: texture_2d<f32>;
+
+#ifdef FILTER_BILINEAR
+fn filter_source(pixel: vec2<f32>) -> vec4<f32> {
+    let uv = (pixel + 0.5) / emulated_size();
+    return textureSampleLevel(source_texture, linear_sampler, viewport_to_target_uv(uv), 0.0);
+}
+#else ifdef FILTER_SHARP_BILINEAR
+// Bilinear, but when the emulated pixels are smaller than the texels of the
+// view the blend between two texels is squeezed into one emulated pixel.
+fn filter_source(pixel: vec2<f32>) -> vec4<f32> {
+    let scale = globals.viewport.zw / emulated_size();
+    let position = globals.viewport.xy + (pixel + 0.5) * scale - 0.5;
+    let texel = floor(position);
+    let blend = saturate((position - texel - 0.5) / min(scale, vec2(1.0)) + 0.5);
+    let uv = (texel + blend + 0.5) * globals.texel_size;
+    return textureSampleLevel(source_texture, linear_sampler, uv, 0.0);
+}
+#else ifdef FILTER_KERNEL
+// The most texels read on each axis. Larger footprints skip texels.
+const MAX_TAPS: f32 = 32.0;
+
+#ifdef FILTER_CUBIC
+// Catmull-Rom spline
+const FILTER_RADIUS: f32 = 2.0;
+
+fn filter_weight(x: f32, width: f32) -> f32 {
+    let t = abs(x);
+    if t < 1.0 {
+        return (1.5 * t - 2.5) * t * t + 1.0;
+    }
+    if t < 2.0 {
+        return ((-0.5 * t + 2.5) * t - 4.0) * t + 2.0;
+    }
+    return 0.0;
+}
+#else ifdef FILTER_LANCZOS
+// Windowed sinc with three lobes
+const FILTER_RADIUS: f32 = 3.0;
+
+fn filter_weight(x: f32, width: f32) -> f32 {
+    let t = abs(x);
+    if t < 1e-4 {
+        return 1.0;
+    }
+    if t >= FILTER_RADIUS {
+        return 0.0;
+    }
+    let angle = 3.1415927 * t;
+    return FILTER_RADIUS * sin(angle) * sin(angle / FILTER_RADIUS) / (angle * angle);
+}
+#else
+// Box, weighted by the part of the texel inside the emulated pixel
+const FILTER_RADIUS: f32 = 0.5;
+
+fn filter_weight(x: f32, width: f32) -> f32 {
+    return saturate((min(x + width * 0.5, 0.5) - max(x - width * 0.5, -0.5)) / width);
+}
+#endif
+
+// Weights the texels of the view around the emulated pixel. The kernel is
+// stretched over the footprint of the emulated pixel, so the whole view
+// contributes to the picture whatever the number of rows.
+fn filter_source(pixel: vec2<f32>) -> vec4<f32> {
+    let scale = globals.viewport.zw / emulated_size();
+    let kernel_scale = max(scale, vec2(1.0));
+    let center = globals.viewport.xy + (pixel + 0.5) * scale;
+    let first = floor(center - FILTER_RADIUS * kernel_scale);
+    let last = ceil(center + FILTER_RADIUS * kernel_scale);
+    let step = max(ceil((last - first) / MAX_TAPS), vec2(1.0));
+    let min_texel = globals.viewport.xy;
+    let max_texel = globals.viewport.xy + globals.viewport.zw - 1.0;
+
+    var sum = vec4(0.0);
+    var total = 0.0;
+    for (var y = first.y; y < last.y; y += step.y) {
+        let tap_y = y + 0.5 * step.y;
+        let weight_y = filter_weight((tap_y - center.y) / kernel_scale.y, step.y / kernel_scale.y);
+        if weight_y == 0.0 {
+            continue;
+        }
+        for (var x = first.x; x < last.x; x += step.x) {
+            let tap_x = x + 0.5 * step.x;
+            let weight = weight_y
+                * filter_weight((tap_x - center.x) / kernel_scale.x, step.x / kernel_scale.x);
+            let texel = clamp(floor(vec2(tap_x, tap_y)), min_texel, max_texel);
+            sum += weight * textureLoad(source_texture, vec2<i32>(texel), 0);
+            total += weight;
+        }
+    }
+    // The negative lobes can ring below black next to bright edges.
+    return max(sum / max(total, 1e-5), vec4(0.0));
+}
+#else
+// Samples the view at the top left corner of the emulated pixel.
+fn filter_source(pixel: vec2<f32>) -> vec4<f32> {
+    let uv = pixel / emulated_size();
+    return textureSampleLevel(source_texture, texture_sampler, viewport_to_target_uv(uv), 0.0);
+}
+#endif
+
+// The snow is part of the signal, so it is mixed per emulated pixel and the
+// later passes treat it like the picture.
+fn apply_snow(color: vec4<f32>, pixel: vec2<u32>) -> vec4<f32> {
+    let snow = random(pixel.x, pixel.y, globals.frame);
+    return vec4(mix(vec3(snow), color.rgb, saturate(settings.signal_strength)), color.a);
+}
+
+// Each texel of the output holds one emulated pixel, filtered from the view
+// by `OldTvSettings::filter`.
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    var color = filter_source(floor(in.position.xy));
+
+#ifdef SNOW
+    color = apply_snow(color, vec2<u32>(in.position.xy));
+#endif
+
+    return color;
+}
@@ -0,0 +1,90 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import bevy_old_tv_shader::common::{
+    texture_sampler, settings, globals, RGB_TO_YIQ, YIQ_TO_RGB, encode_gamma, decode_gamma,
+    random,
+}
+
+// The emulated pixels
+@group(1) @binding(0) var picture_texture: texture_2d<f32>;
+
+// The 3MHz luma of the tape, in cycles over the 52µs of a visible line
+const LUMA_BANDWIDTH: f32 = 156.0;
+// The 0.5MHz chroma of the tape, in cycles per line
+const CHROMA_BANDWIDTH: f32 = 26.0;
+// Speed of the tracking band down the picture, in picture heights per second
+const TRACKING_DRIFT: f32 = 0.07;
+// Largest shift of the rows in the tracking band, in emulated pixels
+const TRACKING_SHIFT: f32 = 12.0;
+// Shift of the last row of the picture when the heads switch, in emulated pixels
+const HEAD_SWITCHING_SHIFT: f32 = 10.0;
+// Taps on each side of the blur at most, however many columns are emulated
+const MAX_TAPS: i32 = 32;
+
+// Returns how deep the row is into the tracking band, from 0 outside to 1 at
+// its center.
+fn tracking_band(y: f32) -> f32 {
+    let error = saturate(settings.vhs_tracking_error);
+    let height = 0.3 * error;
+    let center = fract(globals.time * TRACKING_DRIFT);
+    // The band wraps around from the bottom to the top.
+    let distance = abs(fract(y - center + 0.5) - 0.5);
+    return saturate(1.0 - distance / max(height * 0.5, 1e-4)) * error;
+}
+
+// Returns how far the row is shifted sideways, in emulated pixels.
+fn row_shift(y: f32, row: u32, band: f32) -> f32 {
+    let noise = random(row, globals.frame, 1u) * 2.0 - 1.0;
+    var shift = noise * band * TRACKING_SHIFT;
+
+    // The rows read after the heads switch are pulled to the side.
+    let head_switching = max(settings.vhs_head_switching, 0.0);
+    let below = y - (1.0 - head_switching);
+    if below > 0.0 {
+        let f = below / head_switching;
+        shift += (f * f + noise * 0.2) * HEAD_SWITCHING_SHIFT;
+    }
+    return shift;
+}
+
+// Plays the picture back from a worn tape: the luma and above all the chroma
+// lose their sharpness, the chroma lags behind, and rows get shifted by
+// tracking errors and head switching.
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(picture_texture));
+    let row = u32(in.position.y);
+    let band = tracking_band(in.uv.y);
+    let uv = vec2(in.uv.x - row_shift(in.uv.y, row, band) / size.x, in.uv.y);
+    let chroma_uv = uv - vec2(settings.vhs_chroma_shift / size.x, 0.0);
+
+    let step = 1.0 / size.x;
+    let sigma = vec2(0.1325 / LUMA_BANDWIDTH, 0.1325 / CHROMA_BANDWIDTH);
+    let taps = min(i32(ceil(3.0 * sigma.y / step)), MAX_TAPS);
+
+    var yiq = vec3(0.0);
+    var weights = vec2(0.0);
+    for (var i = -taps; i <= taps; i++) {
+        let offset = f32(i) * step;
+        let weight = exp(-offset * offset / (2.0 * sigma * sigma));
+
+        let luma = textureSampleLevel(picture_texture, texture_sampler, uv + vec2(offset, 0.0), 0.0);
+        let chroma = textureSampleLevel(
+            picture_texture, texture_sampler, chroma_uv + vec2(offset, 0.0), 0.0
+        );
+        yiq += vec3(
+            (RGB_TO_YIQ * encode_gamma(luma.rgb)).x * weight.x,
+            (RGB_TO_YIQ * encode_gamma(chroma.rgb)).yz * weight.y,
+        );
+        weights += weight;
+    }
+    yiq /= vec3(weights.x, weights.y, weights.y);
+
+    // The tracking band shows white streaks where the signal drops out.
+    let streak = random(u32(in.position.x / 4.0), row, globals.frame);
+    if streak > 1.0 - band * 0.2 {
+        yiq = vec3(1.0, 0.0, 0.0);
+    }
+
+    let alpha = textureSampleLevel(picture_texture, texture_sampler, in.uv, 0.0).a;
+    return vec4(decode_gamma(YIQ_TO_RGB * yiq), alpha);
+}